@@ -1,5 +1,6 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use wgpu::{Device, Instance, Queue};
+use wgpu_game_of_life::computer::{CellStorage, Kernel};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let benchmark_name = "Setup Device";
@@ -10,8 +11,44 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 
     let (instance, device, queue) = pollster::block_on(setup_device());
-    test_computer(&instance, &device, &queue, 1024, c);
-    test_computer(&instance, &device, &queue, 2048, c);
+    for kernel in [Kernel::Direct, Kernel::Tiled] {
+        test_computer(
+            &instance,
+            &device,
+            &queue,
+            1024,
+            CellStorage::Ages,
+            kernel,
+            c,
+        );
+        test_computer(
+            &instance,
+            &device,
+            &queue,
+            2048,
+            CellStorage::Ages,
+            kernel,
+            c,
+        );
+    }
+    test_computer(
+        &instance,
+        &device,
+        &queue,
+        2048,
+        CellStorage::Bits,
+        Kernel::default(),
+        c,
+    );
+    test_computer(
+        &instance,
+        &device,
+        &queue,
+        16384,
+        CellStorage::Bits,
+        Kernel::default(),
+        c,
+    );
 }
 
 fn test_computer(
@@ -19,29 +56,37 @@ fn test_computer(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     cells_width: u32,
+    storage: CellStorage,
+    kernel: Kernel,
     criterion: &mut Criterion,
 ) {
     let cells_height = cells_width;
 
-    let creator = wgpu_game_of_life::computer::ComputerFactory::new(device);
+    let creator = wgpu_game_of_life::computer::ComputerFactory::new(device).with_kernel(kernel);
     let seed = 1;
     let initial_density = 50;
     let rule = &wgpu_game_of_life::rules::RULES[0];
-    let mut computer = creator.create(
-        device,
-        cells_width,
-        cells_height,
-        rule,
-        seed,
-        initial_density,
-        queue,
-    );
+    let mut computer = creator
+        .create(
+            device,
+            cells_width,
+            cells_height,
+            storage,
+            rule,
+            wgpu_game_of_life::topology::Topology::Torus,
+            seed,
+            initial_density,
+        )
+        .unwrap();
     instance.poll_all(true);
 
     let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
     queue.submit(std::iter::once(encoder.finish()));
 
-    let benchmark_name = format!("width={cells_width}");
+    let benchmark_name = match storage {
+        CellStorage::Ages => format!("width={cells_width} storage={storage:?} kernel={kernel:?}"),
+        CellStorage::Bits => format!("width={cells_width} storage={storage:?}"),
+    };
     criterion.bench_function(&benchmark_name, |b| {
         b.iter(|| {
             let mut encoder =
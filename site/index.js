@@ -1,32 +1,64 @@
 import init, {
   run,
   getRules,
+  getTopologies,
   setNewRule,
+  setRulestring,
+  setTopology,
   setNewSize,
+  fitGridToWindow,
+  setCellStorage,
   setDensity,
   resetGame,
   togglePause,
   setGenerationsPerSecond,
+  setTurbo,
+  stepTo,
+  cancelStepTo,
+  step,
+  setStepSize,
+  rewindTo,
+  undo,
+  setHistoryLimits,
+  setOscillationAction,
+  takeCensus,
+  saveSession,
+  loadSession,
 } from "./generated/wgpu_game_of_life.js";
 
 const ruleSelect = document.getElementById("rule");
 const sizeSelect = document.getElementById("size");
+const topologySelect = document.getElementById("topology");
+const bitsInput = document.getElementById("bits");
+const rulestringInput = document.getElementById("rulestring");
 const canvas = document.getElementById("webgpu-canvas");
 const overlayElement = document.getElementById("overlay");
 const densityInput = document.getElementById("density");
 const densityDisplay = document.getElementById("density-display");
 const pauseButton = document.getElementById("pauseButton");
+const stepSelect = document.getElementById("step");
+const generationDisplay = document.getElementById("generation-display");
+const populationDisplay = document.getElementById("population-display");
+const oscillationDisplay = document.getElementById("oscillation-display");
+const oscillationActionSelect = document.getElementById("oscillation-action");
 const generationsPerSecondInput = document.getElementById(
   "generations-per-second",
 );
 const generationsPerSecondDisplay = document.getElementById(
   "generations-per-second-display",
 );
+const turboInput = document.getElementById("turbo");
+const timelineInput = document.getElementById("timeline");
+const progressElement = document.getElementById("progress");
+const progressBar = progressElement.querySelector("progress");
+const progressText = document.getElementById("progress-text");
 const aboutDialog = document.getElementById("about");
 const controls = document.getElementById("hideableControls");
 
 canvas.focus();
 
+let currentSize = "512x512";
+
 globalThis.downloadImage = function () {
   const dataUrl = canvas.toDataURL("image/png");
   const a = document.createElement("a");
@@ -38,28 +70,169 @@ globalThis.downloadImage = function () {
   a.click();
 };
 
+globalThis.copyToClipboard = function (text) {
+  navigator.clipboard.writeText(text).catch((e) => {
+    console.error("Failed copying to clipboard", e);
+  });
+};
+
+let currentStateKey = null;
+let currentStatisticsKey = null;
 globalThis.setNewState = function (
   ruleIdx,
-  size,
+  rulestring,
+  width,
+  height,
+  topologyIdx,
+  bits,
   seed,
   density,
   paused,
   generationsPerSecond,
-  _frame,
+  turbo,
+  stepBase,
+  stepExponent,
+  frame,
+  population,
+  births,
+  deaths,
 ) {
+  populationDisplay.textContent = `Population ${population} (+${births} -${deaths})`;
+  // Statistics change every generation, so the rest is not updated when only they changed:
+  const stateKey = [...arguments].slice(0, -3).join();
+  const statisticsKey = [population, births, deaths].join();
+  const onlyStatisticsChanged =
+    stateKey === currentStateKey && statisticsKey !== currentStatisticsKey;
+  currentStateKey = stateKey;
+  currentStatisticsKey = statisticsKey;
+  if (onlyStatisticsChanged) {
+    return;
+  }
   document.title = ruleSelect.options[ruleIdx].textContent;
+  const size = `${width}x${height}`;
+  currentSize = size;
+  if (![...sizeSelect.options].some((option) => option.value === size)) {
+    sizeSelect.insertBefore(
+      new Option(size, size),
+      sizeSelect.querySelector('option[value="custom"]'),
+    );
+  }
   sizeSelect.value = size;
   ruleSelect.value = ruleIdx;
-  const hash = `#rule=${ruleIdx}&size=${size}&seed=${seed}&density=${density}&gps=${generationsPerSecond}`;
+  topologySelect.value = topologyIdx;
+  bitsInput.checked = bits;
+  rulestringInput.value = rulestring;
+  const hash = `#rule=${ruleIdx}&topology=${topologyIdx}&size=${size}&bits=${bits}&seed=${seed}&density=${density}&gps=${generationsPerSecond}`;
   window.history.replaceState({}, "", hash);
 
   pauseButton.textContent = paused ? "Play" : "Pause";
+  generationDisplay.textContent = paused ? `Generation ${frame}` : "";
+  const stepSize = `${stepBase}^${stepExponent}`;
+  if (![...stepSelect.options].some((option) => option.value === stepSize)) {
+    stepSelect.appendChild(new Option(stepSize, stepSize));
+  }
+  stepSelect.value = stepSize;
 
   densityInput.value = density;
   densityDisplay.innerHTML = "&nbsp;0." + density;
 
   generationsPerSecondInput.value = generationsPerSecond;
+  generationsPerSecondInput.disabled = turbo;
   generationsPerSecondDisplay.innerHTML = "&nbsp;" + generationsPerSecond;
+  turboInput.checked = turbo;
+};
+
+globalThis.setProgress = function (generation, target) {
+  if (target === 0n) {
+    progressElement.classList.add("hidden");
+  } else {
+    progressElement.classList.remove("hidden");
+    progressBar.max = Number(target);
+    progressBar.value = Number(generation);
+    progressText.textContent = `Generation ${generation}/${target}`;
+  }
+};
+
+globalThis.setTimeline = function (oldest, latest, current) {
+  timelineInput.min = Number(oldest);
+  timelineInput.max = Number(latest);
+  timelineInput.value = Number(current);
+  timelineInput.disabled = oldest === latest;
+};
+
+globalThis.setOscillation = function (actionIdx, period, generation) {
+  oscillationActionSelect.value = actionIdx;
+  if (period === 0n) {
+    oscillationDisplay.textContent = "";
+  } else if (period === 1n) {
+    oscillationDisplay.textContent = `Static from generation ${generation}`;
+  } else {
+    oscillationDisplay.textContent = `Period ${period} from generation ${generation}`;
+  }
+};
+
+globalThis.showCensus = function (generation, json) {
+  document.getElementById("census-generation").textContent =
+    `Objects at generation ${generation}:`;
+  const rows = JSON.parse(json).objects.map(({ apgcode, kind, period, count }) => {
+    const row = document.createElement("tr");
+    const cells = [count, apgcode, period > 1 ? `${kind}, period ${period}` : kind];
+    for (const content of cells) {
+      const cell = document.createElement("td");
+      cell.append(content);
+      row.append(cell);
+    }
+    return row;
+  });
+  document.getElementById("census-objects").replaceChildren(...rows);
+};
+
+// Sessions are stored in IndexedDB, as they can be larger than what localStorage allows.
+const openSessionDatabase = () =>
+  new Promise((resolve, reject) => {
+    const request = indexedDB.open("game-of-life", 1);
+    request.onupgradeneeded = () => request.result.createObjectStore("sessions");
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+
+globalThis.storeSession = async function (bytes) {
+  try {
+    const database = await openSessionDatabase();
+    const transaction = database.transaction("sessions", "readwrite");
+    transaction.objectStore("sessions").put(bytes.slice(), "session");
+    transaction.oncomplete = () => database.close();
+  } catch (e) {
+    console.error("Failed saving session", e);
+  }
+};
+
+globalThis.requestStoredSession = async function () {
+  try {
+    const database = await openSessionDatabase();
+    const request = database
+      .transaction("sessions", "readonly")
+      .objectStore("sessions")
+      .get("session");
+    request.onsuccess = () => {
+      database.close();
+      if (request.result) {
+        loadSession(request.result);
+      } else {
+        alert("No saved session");
+      }
+    };
+  } catch (e) {
+    console.error("Failed loading session", e);
+  }
+};
+
+globalThis.promptGeneration = function () {
+  const generation = parseInt(prompt("Go to generation:") ?? "");
+  if (generation > 0) {
+    stepTo(generation);
+  }
+  canvas.focus();
 };
 
 globalThis.toggleFullscreen = function () {
@@ -128,8 +301,37 @@ try {
   ruleSelect.addEventListener("change", () => {
     setNewRule(ruleSelect.value);
   });
+  for (const [topologyIdx, topology] of getTopologies().entries()) {
+    topologySelect.appendChild(new Option(topology, topologyIdx));
+  }
+  topologySelect.addEventListener("change", () => {
+    setTopology(topologySelect.value);
+    canvas.focus();
+  });
+  bitsInput.addEventListener("change", () => {
+    setCellStorage(bitsInput.checked);
+    canvas.focus();
+  });
+  rulestringInput.addEventListener("change", () => {
+    setRulestring(rulestringInput.value);
+    canvas.focus();
+  });
   sizeSelect.addEventListener("change", () => {
-    setNewSize(sizeSelect.value);
+    let size = sizeSelect.value;
+    if (size === "fit") {
+      fitGridToWindow();
+    } else {
+      if (size === "custom") {
+        size = prompt("Grid size as WIDTHxHEIGHT:", currentSize) ?? "";
+      }
+      const [width, height] = size.split("x").map((v) => parseInt(v));
+      if (width > 0 && height > 0) {
+        setNewSize(width, height);
+      } else {
+        sizeSelect.value = currentSize;
+      }
+    }
+    canvas.focus();
   });
   document
     .getElementById("downloadButton")
@@ -154,22 +356,81 @@ try {
     document.getElementById("close-dialog").focus();
   });
   pauseButton.addEventListener("click", togglePause);
+  document.getElementById("stepButton").addEventListener("click", step);
+  stepSelect.addEventListener("change", () => {
+    const [base, exponent] = stepSelect.value.split("^").map((v) => parseInt(v));
+    setStepSize(base, exponent);
+    canvas.focus();
+  });
+  document
+    .getElementById("cancelProgressButton")
+    .addEventListener("click", cancelStepTo);
+  timelineInput.addEventListener("input", () => {
+    rewindTo(parseInt(timelineInput.value));
+  });
+  document.getElementById("undoButton").addEventListener("click", () => {
+    undo();
+    canvas.focus();
+  });
+  document.getElementById("saveButton").addEventListener("click", () => {
+    saveSession();
+    canvas.focus();
+  });
+  document.getElementById("loadButton").addEventListener("click", () => {
+    requestStoredSession();
+    canvas.focus();
+  });
   densityInput.addEventListener("change", () => {
     setDensity(densityInput.value);
   });
   generationsPerSecondInput.addEventListener("change", () => {
     setGenerationsPerSecond(generationsPerSecondInput.value);
   });
+  document.getElementById("censusButton").addEventListener("click", () => {
+    takeCensus();
+    canvas.focus();
+  });
+  oscillationActionSelect.addEventListener("change", () => {
+    setOscillationAction(parseInt(oscillationActionSelect.value));
+    canvas.focus();
+  });
+  turboInput.addEventListener("change", () => {
+    setTurbo(turboInput.checked);
+    canvas.focus();
+  });
 
   const urlParams = new URLSearchParams(window.location.hash.substring(1));
   const rule = parseInt(urlParams.get("rule"));
-  const size = parseInt(urlParams.get("size"));
+  const topology = parseInt(urlParams.get("topology"));
+  // Sizes are "WxH", or a single number for square grids in older links.
+  const [width, height = width] = (urlParams.get("size") ?? "")
+    .split("x")
+    .map((v) => parseInt(v));
+  const bits = "true" === urlParams.get("bits");
   const seed = parseInt(urlParams.get("seed"));
   const density = parseInt(urlParams.get("density"));
   const paused = "true" === urlParams.get("paused");
   const generationsPerSecond = parseInt(urlParams.get("gps"));
 
-  await run(rule, size, seed, density, paused, generationsPerSecond);
+  await run(
+    rule,
+    topology,
+    width,
+    height,
+    bits,
+    seed,
+    density,
+    paused,
+    generationsPerSecond,
+  );
+
+  // Snapshot cadence in generations and memory budget in megabytes, as in "100,128".
+  const [historyCadence, historyMegabytes] = (urlParams.get("history") ?? "")
+    .split(",")
+    .map((v) => parseInt(v));
+  if (historyCadence > 0 && historyMegabytes >= 0) {
+    setHistoryLimits(historyCadence, historyMegabytes);
+  }
 } catch (e) {
   console.error("error", e);
   canvas.remove();
@@ -1,27 +1,69 @@
 use jni::{JavaVM, objects::JObject, sys::JNIInvokeInterface_};
+use std::path::PathBuf;
+use std::sync::OnceLock;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
 use winit::window::WindowId;
 
+use crate::event_loop::UserEvent;
+
+/// The directory where the app can store files, set when started.
+static INTERNAL_DATA_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// The file where the session is saved when suspended, to be restored if the
+/// process is killed while in the background.
+pub(crate) fn session_path() -> PathBuf {
+    INTERNAL_DATA_PATH
+        .get()
+        .cloned()
+        .unwrap_or_default()
+        .join("game-of-life.session")
+}
+
 struct AndroidApp {
     state: Option<crate::State>,
+    proxy: EventLoopProxy<UserEvent>,
 }
 
-impl ApplicationHandler for AndroidApp {
+impl ApplicationHandler<UserEvent> for AndroidApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window_attributes = winit::window::Window::default_attributes();
         let window = event_loop.create_window(window_attributes).unwrap();
-        self.state = Some(
-            pollster::block_on(crate::State::new(
-                window, None, None, None, None, false, None,
-            ))
-            .unwrap(),
-        );
+        if let Some(state) = &mut self.state {
+            // Only the surface needs to be recreated for the new window:
+            if let Err(e) = state.resume(window) {
+                log::error!("{e}");
+            }
+            return;
+        }
+        let mut state = pollster::block_on(crate::State::new(
+            window, None, None, None, None, None, None, false, None,
+        ))
+        .unwrap();
+        crate::event_loop::set_wake_up(&mut state, &self.proxy);
+        if let Ok(bytes) = crate::session::stored()
+            && let Err(e) = state.load_session(&bytes)
+        {
+            log::error!("{e}");
+        }
+        self.state = Some(state);
     }
 
     fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
-        self.state = None;
+        if let Some(state) = &mut self.state {
+            // Saved in case the process is killed while in the background:
+            state.store_session();
+            state.suspend();
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        if let Some(state) = &mut self.state {
+            match event {
+                UserEvent::AdvanceBatchDone => state.continue_advance(),
+            }
+        }
     }
 
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
@@ -51,9 +93,18 @@ fn android_main(app: winit::platform::android::activity::AndroidApp) {
     );
 
     enable_immersive(&app);
+    if let Some(path) = app.internal_data_path() {
+        INTERNAL_DATA_PATH.set(path).ok();
+    }
 
-    let event_loop = EventLoop::builder().with_android_app(app).build().unwrap();
-    let mut android_app = AndroidApp { state: None };
+    let event_loop = EventLoop::<UserEvent>::with_user_event()
+        .with_android_app(app)
+        .build()
+        .unwrap();
+    let mut android_app = AndroidApp {
+        state: None,
+        proxy: event_loop.create_proxy(),
+    };
     let _ = event_loop.run_app(&mut android_app);
 }
 
@@ -0,0 +1,198 @@
+//! Advance a pattern on an unbounded plane using [HashLife](https://conwaylife.com/wiki/HashLife),
+//! by up to 2^63 generations at a time.
+//!
+//! The pattern is read as RLE or Golly's macrocell format, and advanced by steps of a power
+//! of two generations, after which the generation, population and bounds are written as
+//! JSON. A window of the plane can be saved as a session to view and continue it in the
+//! desktop version.
+//!
+//! ```sh
+//! cargo run --release --bin hashlife -- --pattern gun.rle --step 30 --session game-of-life.session
+//! ```
+
+fn main() {
+    #[cfg(not(any(target_os = "android", target_family = "wasm")))]
+    {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+        if let Err(e) = advance::Options::parse(std::env::args().skip(1))
+            .and_then(|options| advance::run(&options))
+        {
+            log::error!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_family = "wasm")))]
+mod advance {
+    use std::io::Write as _;
+    use std::path::{Path, PathBuf};
+
+    use wgpu_game_of_life::computer::CellStorage;
+    use wgpu_game_of_life::hashlife::HashLife;
+    use wgpu_game_of_life::rules;
+    use wgpu_game_of_life::session::Session;
+    use wgpu_game_of_life::topology::Topology;
+
+    const USAGE: &str = "Usage: hashlife --pattern PATTERN.rle|PATTERN.mc [--step 10] \
+        [--steps 1] [--window 512x512] [--at X,Y] [--bits] [--session game-of-life.session]";
+
+    pub struct Options {
+        pattern: PathBuf,
+        log2_generations: u8,
+        steps: u64,
+        cells_width: u32,
+        cells_height: u32,
+        /// The centre of the window, or the centre of the live cells if not given.
+        at: Option<(i64, i64)>,
+        cell_storage: CellStorage,
+        session: Option<PathBuf>,
+    }
+
+    impl Options {
+        pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+            let mut pattern = None;
+            let mut log2_generations = 10;
+            let mut steps = 1;
+            let (mut cells_width, mut cells_height) = (512, 512);
+            let mut at = None;
+            let mut cell_storage = CellStorage::Ages;
+            let mut session = None;
+            while let Some(arg) = args.next() {
+                let mut value = || args.next().ok_or_else(|| format!("Missing value of {arg}"));
+                let invalid = |value: &str| format!("Invalid value of {arg}: '{value}'");
+                match arg.as_str() {
+                    "--pattern" => pattern = Some(value()?.into()),
+                    "--step" => {
+                        let value = value()?;
+                        log2_generations = value
+                            .parse()
+                            .ok()
+                            .filter(|&log2_generations| log2_generations < 64)
+                            .ok_or_else(|| invalid(&value))?;
+                    }
+                    "--steps" => {
+                        let value = value()?;
+                        steps = value.parse().map_err(|_| invalid(&value))?;
+                    }
+                    "--window" => {
+                        let value = value()?;
+                        (cells_width, cells_height) = value
+                            .split_once('x')
+                            .and_then(|(width, height)| {
+                                Some((width.parse().ok()?, height.parse().ok()?))
+                            })
+                            .filter(|&(width, height)| width > 0 && height > 0)
+                            .ok_or_else(|| invalid(&value))?;
+                    }
+                    "--at" => {
+                        let value = value()?;
+                        at = Some(
+                            value
+                                .split_once(',')
+                                .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                                .ok_or_else(|| invalid(&value))?,
+                        );
+                    }
+                    "--bits" => cell_storage = CellStorage::Bits,
+                    "--session" => session = Some(value()?.into()),
+                    _ => return Err(format!("Unknown argument '{arg}'\n{USAGE}")),
+                }
+            }
+            Ok(Self {
+                pattern: pattern.ok_or_else(|| format!("Missing --pattern\n{USAGE}"))?,
+                log2_generations,
+                steps,
+                cells_width,
+                cells_height,
+                at,
+                cell_storage,
+                session,
+            })
+        }
+    }
+
+    pub fn run(options: &Options) -> Result<(), String> {
+        let text = std::fs::read_to_string(&options.pattern)
+            .map_err(|e| format!("Failed reading {}: {e}", options.pattern.display()))?;
+        let mut hashlife = if text.starts_with("[M2]") {
+            HashLife::from_macrocell(&text)?
+        } else {
+            HashLife::from_rle(&text)?
+        };
+
+        for _ in 0..options.steps {
+            hashlife.step(options.log2_generations)?;
+            log::info!(
+                "Generation {} with population {}",
+                hashlife.generation(),
+                hashlife.population()
+            );
+        }
+
+        if let Some(path) = &options.session {
+            save_session(options, &hashlife, path)?;
+        }
+        let bounds = hashlife.bounds().map_or_else(
+            || "null".to_string(),
+            |(left, bottom, right, top)| format!("[{left},{bottom},{right},{top}]"),
+        );
+        writeln!(
+            std::io::stdout(),
+            r#"{{"rule":"{}","generation":{},"population":{},"bounds":{bounds}}}"#,
+            hashlife.rulestring(),
+            hashlife.generation(),
+            hashlife.population()
+        )
+        .map_err(|e| format!("Failed writing output: {e}"))
+    }
+
+    /// Save the window as a session on a plane of the window size.
+    fn save_session(options: &Options, hashlife: &HashLife, path: &Path) -> Result<(), String> {
+        let rulestring = hashlife.rulestring();
+        let rule = rules::RULES
+            .iter()
+            .find(|rule| rule.rulestring() == rulestring)
+            .ok_or_else(|| format!("Sessions do not support the rule {rulestring}"))?;
+        let (x, y) = options
+            .at
+            .or_else(|| {
+                hashlife.bounds().map(|(left, bottom, right, top)| {
+                    (left + (right - left) / 2, bottom + (top - bottom) / 2)
+                })
+            })
+            .unwrap_or((0, 0));
+        let (left, bottom) = (
+            x - i64::from(options.cells_width / 2),
+            y - i64::from(options.cells_height / 2),
+        );
+        let session = Session {
+            rulestring,
+            topology: Topology::Plane,
+            cells_width: options.cells_width,
+            cells_height: options.cells_height,
+            cell_storage: options.cell_storage,
+            generation: hashlife.generation(),
+            seed: 0,
+            initial_density: rule.initial_density,
+            generations_per_second: 30,
+            words: hashlife.window_words(
+                left,
+                bottom,
+                options.cells_width,
+                options.cells_height,
+                options.cell_storage,
+            ),
+        };
+        std::fs::write(path, session.to_bytes()?)
+            .map_err(|e| format!("Failed writing {}: {e}", path.display()))?;
+        log::info!(
+            "Saved the window from ({left}, {bottom}) of {}x{} cells to {}",
+            options.cells_width,
+            options.cells_height,
+            path.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+//! Search random soups for objects without a window, like
+//! [apgsearch](https://conwaylife.com/wiki/Apgsearch).
+//!
+//! Each seed is a soup run on the GPU until it has settled into a static or periodic grid,
+//! after which a census of its objects is taken. Batches of soups are run together, each
+//! advancing in the same dispatches. Without a GPU adapter, or with `--cpu`, the soups of a
+//! batch are instead run on the CPU by a thread each. The counts over all soups are written
+//! as JSON, along with up to 10 seeds of soups containing each object.
+//!
+//! ```sh
+//! cargo run --release --bin soup-search -- --rule B3/S23:T128,128 --seeds 0..1000 --output census.json
+//! ```
+
+fn main() {
+    #[cfg(not(any(target_os = "android", target_family = "wasm")))]
+    {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+        if let Err(e) = search::Options::parse(std::env::args().skip(1))
+            .and_then(|options| search::run(&options))
+        {
+            log::error!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_family = "wasm")))]
+mod search {
+    use std::collections::HashMap;
+    use std::io::Write as _;
+    use std::num::NonZeroUsize;
+    use std::ops::Range;
+    use std::sync::{Arc, Mutex};
+
+    use wgpu_game_of_life::census::{Census, ObjectKind};
+    use wgpu_game_of_life::computer::{CellStorage, Cells, Computer, ComputerFactory, Statistics};
+    use wgpu_game_of_life::cpu::CpuSimulation;
+    use wgpu_game_of_life::oscillation::{Oscillation, OscillationDetector};
+    use wgpu_game_of_life::rules;
+    use wgpu_game_of_life::simulation::{self, Settings};
+
+    const USAGE: &str = "Usage: soup-search [--rule B3/S23:T128,128] [--seeds 0..100] \
+        [--density 50] [--bits] [--batch 64] [--max-generations 100000] [--software] \
+        [--cpu] [--output census.json]";
+
+    /// The generations advanced between checking if a soup has settled, which is divisible
+    /// by the common periods 2, 3, 4, 5, 6 and 15 so that they are detected right away.
+    const CHECK_INTERVAL: u64 = 60;
+
+    /// The generations after a multiple of [`CHECK_INTERVAL`] at which soups are also checked
+    /// once one first repeats, as checks at multiples alone find a multiple of the period
+    /// when the period does not divide [`CHECK_INTERVAL`]. One of these is divisible by the
+    /// greatest common divisor of the two, which narrows it down to the period itself.
+    const NARROWING_OFFSETS: [u64; 11] = [1, 2, 3, 4, 5, 6, 10, 12, 15, 20, 30];
+
+    /// The number of seeds listed for each object found.
+    const SAMPLE_SEEDS: usize = 10;
+
+    pub struct Options {
+        /// The settings of each soup, apart from the seed.
+        settings: Settings,
+        seeds: Range<u32>,
+        batch: u32,
+        max_generations: u64,
+        software: bool,
+        cpu: bool,
+        output: Option<std::path::PathBuf>,
+    }
+
+    impl Options {
+        pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+            let mut rulestring = "B3/S23:T128,128".to_string();
+            let mut seeds = 0..100;
+            let mut initial_density = None;
+            let mut cell_storage = CellStorage::Ages;
+            let mut batch = 64;
+            let mut max_generations = 100_000;
+            let mut software = false;
+            let mut cpu = false;
+            let mut output = None;
+            while let Some(arg) = args.next() {
+                let mut value = || args.next().ok_or_else(|| format!("Missing value of {arg}"));
+                let invalid = |value: &str| format!("Invalid value of {arg}: '{value}'");
+                match arg.as_str() {
+                    "--rule" => rulestring = value()?,
+                    "--seeds" => {
+                        let value = value()?;
+                        let (start, end) = value.split_once("..").ok_or_else(|| invalid(&value))?;
+                        seeds = start.parse().map_err(|_| invalid(&value))?
+                            ..end.parse().map_err(|_| invalid(&value))?;
+                    }
+                    "--density" => {
+                        let value = value()?;
+                        initial_density = Some(
+                            value
+                                .parse()
+                                .ok()
+                                .filter(|density| (1..100).contains(density))
+                                .ok_or_else(|| invalid(&value))?,
+                        );
+                    }
+                    "--bits" => cell_storage = CellStorage::Bits,
+                    "--batch" => {
+                        let value = value()?;
+                        batch = value
+                            .parse()
+                            .ok()
+                            .filter(|&batch| batch > 0)
+                            .ok_or_else(|| invalid(&value))?;
+                    }
+                    "--max-generations" => {
+                        let value = value()?;
+                        max_generations = value.parse().map_err(|_| invalid(&value))?;
+                    }
+                    "--software" => software = true,
+                    "--cpu" => cpu = true,
+                    "--output" => output = Some(value()?.into()),
+                    _ => return Err(format!("Unknown argument '{arg}'\n{USAGE}")),
+                }
+            }
+
+            let parsed = rules::parse_rulestring(&rulestring)?;
+            let rule_idx = rules::RULES
+                .iter()
+                .position(|rule| (rule.born, rule.survives) == (parsed.born, parsed.survives))
+                .ok_or_else(|| format!("Unsupported rule: '{rulestring}'"))?;
+            let (cells_width, cells_height) = parsed.size.unwrap_or((128, 128));
+            let settings = Settings {
+                rule_idx: rule_idx as u32,
+                topology: parsed.topology,
+                cells_width,
+                cells_height,
+                cell_storage,
+                seed: seeds.start,
+                initial_density: initial_density.unwrap_or(rules::RULES[rule_idx].initial_density),
+            };
+            Ok(Self {
+                settings,
+                seeds,
+                batch,
+                max_generations,
+                software,
+                cpu,
+                output,
+            })
+        }
+    }
+
+    /// The soups an object has been found in.
+    struct Found {
+        kind: ObjectKind,
+        period: u64,
+        count: u64,
+        seeds: Vec<u32>,
+    }
+
+    pub fn run(options: &Options) -> Result<(), String> {
+        let gpu = if options.cpu {
+            None
+        } else {
+            let instance =
+                wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+            match pollster::block_on(simulation::request_device(
+                &instance,
+                None,
+                options.software,
+            )) {
+                Ok((adapter, device, queue)) => {
+                    log::info!("Using {}", adapter.get_info().name);
+                    let factory = ComputerFactory::new(&device);
+                    Some((device, queue, factory))
+                }
+                Err(e) => {
+                    log::warn!("Simulating on the CPU: {e}");
+                    None
+                }
+            }
+        };
+        let settings = options.settings;
+        let mut found: HashMap<String, Found> = HashMap::new();
+        let mut unsettled_seeds = Vec::new();
+        let seeds: Vec<u32> = options.seeds.clone().collect();
+        for seeds in seeds.chunks(options.batch as usize) {
+            let settled = match &gpu {
+                Some((device, queue, factory)) => {
+                    let mut computer = factory.create_batch(
+                        device,
+                        settings.cells_width,
+                        settings.cells_height,
+                        settings.cell_storage,
+                        settings.rule(),
+                        settings.topology,
+                        seeds,
+                        settings.initial_density,
+                    )?;
+                    let oscillations =
+                        settle(device, queue, &mut computer, options.max_generations)?;
+                    oscillations
+                        .into_iter()
+                        .zip(pollster::block_on(
+                            computer.read_batch_cells(device, queue),
+                        )?)
+                        .collect()
+                }
+                None => settle_on_cpu(settings, seeds, options.max_generations)?,
+            };
+
+            for (&seed, (oscillation, cells)) in seeds.iter().zip(settled) {
+                let Some(oscillation) = oscillation else {
+                    log::info!(
+                        "Soup {seed} has not settled after {} generations",
+                        options.max_generations
+                    );
+                    unsettled_seeds.push(seed);
+                    continue;
+                };
+
+                let census = Census::take(
+                    settings.rule(),
+                    settings.topology,
+                    settings.cells_width,
+                    settings.cells_height,
+                    oscillation.period,
+                    |x, y| cells.get(x, y) > 0,
+                );
+                log::info!(
+                    "Soup {seed} settled at generation {} with {} objects",
+                    oscillation.generation,
+                    census.entries.iter().map(|entry| entry.count).sum::<u32>()
+                );
+                for entry in census.entries {
+                    let found = found.entry(entry.apgcode).or_insert(Found {
+                        kind: entry.kind,
+                        period: entry.period,
+                        count: 0,
+                        seeds: Vec::new(),
+                    });
+                    found.count += u64::from(entry.count);
+                    if found.seeds.len() < SAMPLE_SEEDS {
+                        found.seeds.push(seed);
+                    }
+                }
+            }
+        }
+
+        let json = to_json(options, &found, &unsettled_seeds);
+        match &options.output {
+            Some(path) => std::fs::write(path, json)
+                .map_err(|e| format!("Failed writing {}: {e}", path.display())),
+            None => writeln!(std::io::stdout(), "{json}")
+                .map_err(|e| format!("Failed writing output: {e}")),
+        }
+    }
+
+    /// Advance soups on the CPU until each repeats, as [`settle()`] does on the GPU, running
+    /// the soups on a thread each.
+    fn settle_on_cpu(
+        settings: Settings,
+        seeds: &[u32],
+        max_generations: u64,
+    ) -> Result<Vec<(Option<Oscillation>, Cells)>, String> {
+        let threads = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let mut settled = Vec::with_capacity(seeds.len());
+        for seeds in seeds.chunks(threads) {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = seeds
+                    .iter()
+                    .map(|&seed| {
+                        scope.spawn(move || -> Result<_, String> {
+                            let mut simulation = CpuSimulation::new(Settings { seed, ..settings })?;
+                            let mut detector = OscillationDetector::default();
+                            loop {
+                                detector.record(simulation.generation(), simulation.statistics());
+                                if detector.oscillation().is_some() {
+                                    for step in narrowing_steps() {
+                                        simulation.step(step);
+                                        detector.record(
+                                            simulation.generation(),
+                                            simulation.statistics(),
+                                        );
+                                    }
+                                    return Ok((detector.oscillation(), simulation.cells()));
+                                }
+                                if simulation.generation() >= max_generations {
+                                    return Ok((None, simulation.cells()));
+                                }
+                                simulation.step(CHECK_INTERVAL);
+                            }
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    settled.push(
+                        handle
+                            .join()
+                            .map_err(|_| "Soup thread panicked".to_string())??,
+                    );
+                }
+                Ok::<_, String>(())
+            })?;
+        }
+        Ok(settled)
+    }
+
+    /// Advance a batch of soups until each repeats, returning how they repeat, with `None`
+    /// for soups that have not within `max_generations`.
+    ///
+    /// Soups that have settled keep being advanced with the rest of the batch, which
+    /// does not change the objects they hold.
+    fn settle(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        computer: &mut Computer,
+        max_generations: u64,
+    ) -> Result<Vec<Option<Oscillation>>, String> {
+        let mut detectors: Vec<OscillationDetector> = (0..computer.batch())
+            .map(|_| OscillationDetector::default())
+            .collect();
+        // Whether the period of each soup has been narrowed down since it first repeated:
+        let mut narrowed = vec![false; detectors.len()];
+        let mut generation = 0;
+        record_batch(device, queue, computer, generation, &mut detectors)?;
+        loop {
+            if generation >= max_generations || narrowed.iter().all(|&narrowed| narrowed) {
+                return Ok(detectors
+                    .iter()
+                    .map(OscillationDetector::oscillation)
+                    .collect());
+            }
+            let repeated: Vec<bool> = detectors
+                .iter()
+                .map(|detector| detector.oscillation().is_some())
+                .collect();
+            let steps: Vec<u64> = if repeated == narrowed {
+                vec![CHECK_INTERVAL]
+            } else {
+                narrowed = repeated;
+                narrowing_steps().collect()
+            };
+            for step in steps {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("soup_encoder"),
+                });
+                computer.enqueue_generations(&mut encoder, step);
+                queue.submit(std::iter::once(encoder.finish()));
+                generation += step;
+                record_batch(device, queue, computer, generation, &mut detectors)?;
+            }
+        }
+    }
+
+    /// The generations to advance from a multiple of [`CHECK_INTERVAL`] to each of the
+    /// [`NARROWING_OFFSETS`] in turn and then to the next multiple.
+    fn narrowing_steps() -> impl Iterator<Item = u64> {
+        NARROWING_OFFSETS
+            .into_iter()
+            .chain([CHECK_INTERVAL])
+            .scan(0, |previous, offset| {
+                let step = offset - *previous;
+                *previous = offset;
+                Some(step)
+            })
+    }
+
+    fn record_batch(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        computer: &Computer,
+        generation: u64,
+        detectors: &mut [OscillationDetector],
+    ) -> Result<(), String> {
+        let statistics = read_batch_statistics(device, queue, computer)?;
+        for (detector, statistics) in detectors.iter_mut().zip(statistics) {
+            detector.record(generation, statistics);
+        }
+        Ok(())
+    }
+
+    fn read_batch_statistics(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        computer: &Computer,
+    ) -> Result<Vec<Statistics>, String> {
+        let result = Arc::new(Mutex::new(None));
+        let result_in_callback = Arc::clone(&result);
+        computer.batch_statistics(device, queue, move |statistics| {
+            if let Ok(mut result) = result_in_callback.lock() {
+                *result = Some(statistics);
+            }
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("Failed waiting for statistics: {e}"))?;
+        result
+            .lock()
+            .ok()
+            .and_then(|mut result| result.take())
+            .ok_or_else(|| "Failed reading statistics".to_string())
+    }
+
+    fn to_json(
+        options: &Options,
+        found: &HashMap<String, Found>,
+        unsettled_seeds: &[u32],
+    ) -> String {
+        let join = |seeds: &[u32]| {
+            seeds
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut objects: Vec<(&String, &Found)> = found.iter().collect();
+        objects.sort_by(|(a_apgcode, a), (b_apgcode, b)| {
+            b.count.cmp(&a.count).then_with(|| a_apgcode.cmp(b_apgcode))
+        });
+        let objects: Vec<String> = objects
+            .into_iter()
+            .map(|(apgcode, found)| {
+                format!(
+                    r#"{{"apgcode":"{apgcode}","kind":"{}","period":{},"count":{},"seeds":[{}]}}"#,
+                    found.kind.name(),
+                    found.period,
+                    found.count,
+                    join(&found.seeds)
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"rule":"{}","topology":"{}","width":{},"height":{},"density":{},"seeds":[{},{}],"unsettled_seeds":[{}],"objects":[{}]}}"#,
+            options.settings.rule().rulestring(),
+            options.settings.topology.name(),
+            options.settings.cells_width,
+            options.settings.cells_height,
+            options.settings.initial_density,
+            options.seeds.start,
+            options.seeds.end,
+            join(unsettled_seeds),
+            objects.join(",")
+        )
+    }
+}
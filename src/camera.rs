@@ -0,0 +1,223 @@
+use crate::selection::Rect;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// The part of the grid shown, in grid coordinates where `(0, 0)` is the bottom left
+/// and `(1, 1)` the top right corner of the grid.
+///
+/// Cells are always shown as squares, so the grid is letterboxed if its aspect ratio
+/// differs from that of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Camera {
+    /// The grid position shown at the center of the window.
+    pub(crate) center: (f32, f32),
+    /// How many times the grid is magnified, where 1 fits the whole grid in the window.
+    pub(crate) zoom: f32,
+    cells_size: (u32, u32),
+    surface_size: PhysicalSize<u32>,
+}
+
+impl Camera {
+    /// The maximum zoom, at which this many cells are shown across the window.
+    const MIN_VISIBLE_CELLS: f32 = 4.;
+
+    pub(crate) const fn new(
+        cells_width: u32,
+        cells_height: u32,
+        surface_size: PhysicalSize<u32>,
+    ) -> Self {
+        Self {
+            center: (0.5, 0.5),
+            zoom: 1.,
+            cells_size: (cells_width, cells_height),
+            surface_size,
+        }
+    }
+
+    /// Update the grid and window sizes, keeping the center and zoom.
+    pub(crate) const fn set_sizes(
+        &mut self,
+        cells_width: u32,
+        cells_height: u32,
+        surface_size: PhysicalSize<u32>,
+    ) {
+        self.cells_size = (cells_width, cells_height);
+        self.surface_size = surface_size;
+    }
+
+    /// How much the grid is magnified horizontally and vertically in clip space,
+    /// where a scale of 1 maps the grid onto the whole window.
+    fn scale(&self) -> (f32, f32) {
+        let grid_aspect_ratio = self.cells_size.0 as f32 / self.cells_size.1 as f32;
+        let surface_aspect_ratio =
+            self.surface_size.width.max(1) as f32 / self.surface_size.height.max(1) as f32;
+        if grid_aspect_ratio > surface_aspect_ratio {
+            (
+                self.zoom,
+                self.zoom * surface_aspect_ratio / grid_aspect_ratio,
+            )
+        } else {
+            (
+                self.zoom * grid_aspect_ratio / surface_aspect_ratio,
+                self.zoom,
+            )
+        }
+    }
+
+    /// The camera uniform used by the render shader, as `(center, scale)`.
+    pub(crate) fn uniform_array(&self) -> [f32; 4] {
+        let (scale_x, scale_y) = self.scale();
+        [self.center.0, self.center.1, scale_x, scale_y]
+    }
+
+    /// The position in the window, in clip space coordinates ranging from -1 to 1.
+    fn clip_position(&self, position: PhysicalPosition<f64>) -> (f32, f32) {
+        (
+            (position.x / f64::from(self.surface_size.width) * 2. - 1.) as f32,
+            (1. - position.y / f64::from(self.surface_size.height) * 2.) as f32,
+        )
+    }
+
+    /// The grid position shown at a position in the window.
+    ///
+    /// This is the inverse of the transform done in the render shader.
+    pub(crate) fn grid_position(&self, position: PhysicalPosition<f64>) -> (f32, f32) {
+        let (clip_x, clip_y) = self.clip_position(position);
+        let (scale_x, scale_y) = self.scale();
+        (
+            self.center.0 + clip_x / (2. * scale_x),
+            self.center.1 + clip_y / (2. * scale_y),
+        )
+    }
+
+    /// The cell shown at a position in the window, if any.
+    pub(crate) fn cell_at(&self, position: PhysicalPosition<f64>) -> Option<(u32, u32)> {
+        let (x, y) = self.grid_position(position);
+        if !(0. ..1.).contains(&x) || !(0. ..1.).contains(&y) {
+            return None;
+        }
+        let (cells_width, cells_height) = self.cells_size;
+        Some((
+            ((x * cells_width as f32) as u32).min(cells_width - 1),
+            ((y * cells_height as f32) as u32).min(cells_height - 1),
+        ))
+    }
+
+    fn max_zoom(&self) -> f32 {
+        (self.cells_size.0.max(self.cells_size.1) as f32 / Self::MIN_VISIBLE_CELLS).max(1.)
+    }
+
+    /// Multiply the zoom by `factor`, keeping the grid position at `position` in place.
+    pub(crate) fn zoom_around(&mut self, factor: f32, position: PhysicalPosition<f64>) {
+        let (grid_x, grid_y) = self.grid_position(position);
+        self.zoom = (self.zoom * factor).clamp(1., self.max_zoom());
+        let (new_grid_x, new_grid_y) = self.grid_position(position);
+        self.center = (
+            self.center.0 + grid_x - new_grid_x,
+            self.center.1 + grid_y - new_grid_y,
+        );
+        self.clamp_center();
+    }
+
+    /// Move the camera so that the grid position at `from` ends up at `to`.
+    pub(crate) fn drag(&mut self, from: PhysicalPosition<f64>, to: PhysicalPosition<f64>) {
+        let (from_x, from_y) = self.grid_position(from);
+        let (to_x, to_y) = self.grid_position(to);
+        self.center = (self.center.0 + from_x - to_x, self.center.1 + from_y - to_y);
+        self.clamp_center();
+    }
+
+    /// Move the camera by a fraction of the visible part of the grid.
+    pub(crate) fn pan(&mut self, dx: f32, dy: f32) {
+        self.center = (
+            self.center.0 + dx / self.zoom,
+            self.center.1 + dy / self.zoom,
+        );
+        self.clamp_center();
+    }
+
+    /// Center on and zoom in on a rectangle of cells, leaving a margin around it.
+    pub(crate) fn fit(&mut self, rect: Rect) {
+        const MARGIN: f32 = 1.2;
+        let (width, height) = (self.cells_size.0 as f32, self.cells_size.1 as f32);
+        self.center = (
+            (rect.x as f32 + rect.width as f32 / 2.) / width,
+            (rect.y as f32 + rect.height as f32 / 2.) / height,
+        );
+        self.zoom = (width / (rect.width as f32 * MARGIN))
+            .min(height / (rect.height as f32 * MARGIN))
+            .clamp(1., self.max_zoom());
+        self.clamp_center();
+    }
+
+    const fn clamp_center(&mut self) {
+        self.center = (self.center.0.clamp(0., 1.), self.center.1.clamp(0., 1.));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera() {
+        let mut camera = Camera::new(64, 64, PhysicalSize::new(200, 200));
+        assert_eq!(
+            camera.cell_at(PhysicalPosition::new(0.5, 0.5)),
+            Some((0, 63))
+        );
+        assert_eq!(
+            camera.cell_at(PhysicalPosition::new(199.9, 199.9)),
+            Some((63, 0))
+        );
+        assert_eq!(camera.cell_at(PhysicalPosition::new(-1., 50.)), None);
+
+        let cursor = PhysicalPosition::new(40., 30.);
+        let cell_at_cursor = camera.cell_at(cursor);
+        camera.zoom_around(4., cursor);
+        assert_eq!(camera.zoom, 4.);
+        assert_eq!(camera.cell_at(cursor), cell_at_cursor);
+        camera.zoom_around(1000., cursor);
+        assert_eq!(camera.zoom, 16.);
+
+        let center = PhysicalPosition::new(105., 95.);
+        let cell_at_center = camera.cell_at(center);
+        camera.drag(cursor, center);
+        assert_eq!(camera.cell_at(center), cell_at_cursor);
+        camera.drag(center, cursor);
+        assert_eq!(camera.cell_at(center), cell_at_center);
+
+        camera.fit(Rect {
+            x: 8,
+            y: 16,
+            width: 8,
+            height: 4,
+        });
+        assert_eq!(camera.center, (0.1875, 0.28125));
+        assert!((camera.zoom - 64. / (8. * 1.2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_letterboxing() {
+        // A square grid in a wide window has empty space to the left and right:
+        let camera = Camera::new(64, 64, PhysicalSize::new(200, 100));
+        assert_eq!(camera.uniform_array(), [0.5, 0.5, 0.5, 1.]);
+        assert_eq!(camera.cell_at(PhysicalPosition::new(40., 50.)), None);
+        assert_eq!(
+            camera.cell_at(PhysicalPosition::new(50.5, 0.5)),
+            Some((0, 63))
+        );
+        assert_eq!(
+            camera.cell_at(PhysicalPosition::new(149.5, 99.5)),
+            Some((63, 0))
+        );
+
+        // A wide grid in a square window has empty space above and below:
+        let camera = Camera::new(128, 32, PhysicalSize::new(100, 100));
+        assert_eq!(camera.uniform_array(), [0.5, 0.5, 1., 0.25]);
+        assert_eq!(camera.cell_at(PhysicalPosition::new(50., 30.)), None);
+        assert_eq!(
+            camera.cell_at(PhysicalPosition::new(0.5, 37.6)),
+            Some((0, 31))
+        );
+    }
+}
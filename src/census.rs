@@ -0,0 +1,486 @@
+//! A census of the objects in a settled grid, counting still lifes, oscillators and
+//! spaceships by their [apgcode](https://conwaylife.com/wiki/Apgcode) like Catagolue does.
+//!
+//! Objects are found from the cells alive in any generation of a period of the grid, where
+//! cells at most two cells apart are grouped together, and a group is split into its
+//! 8-connected parts if they do not interact. Each object is then evolved on its own to
+//! find how it repeats. Objects crossing the
+//! edges of the grid are followed around a torus, while other boundaries are treated as a
+//! plane, so objects crossing twisted edges may end up as unknown.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::rules::Rule;
+use crate::topology::Topology;
+
+/// The most generations that objects are evolved to find their period.
+const MAX_PERIOD: u64 = 500;
+/// Objects growing beyond this many cells are not evolved further.
+const MAX_POPULATION: usize = 2000;
+/// The characters used for columns of cells and lengths of zero runs in apgcodes.
+const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+type Cell = (i32, i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    /// Not repeating on its own within a limited number of generations, such as objects
+    /// still interacting with each other or growing forever.
+    Unknown,
+}
+
+impl ObjectKind {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::StillLife => "still life",
+            Self::Oscillator => "oscillator",
+            Self::Spaceship => "spaceship",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// The number of objects with the same apgcode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CensusEntry {
+    /// The apgcode of the object, such as `xs4_33` for a block or `xq4_153` for a glider.
+    /// Unknown objects get an `xx` prefix followed by their population.
+    pub apgcode: String,
+    pub kind: ObjectKind,
+    /// The number of generations after which the object repeats, or 0 if unknown.
+    pub period: u64,
+    pub count: u32,
+}
+
+/// The objects in a grid, the most common first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+}
+
+impl Census {
+    /// Count the objects in a grid of the given size, where `is_alive(x, y)` tells if a
+    /// cell is alive and `period` is how often the grid repeats, or 1 if unknown.
+    pub fn take(
+        rule: &Rule,
+        topology: Topology,
+        cells_width: u32,
+        cells_height: u32,
+        period: u64,
+        is_alive: impl Fn(u32, u32) -> bool,
+    ) -> Self {
+        let space = match topology {
+            Topology::Torus => Space::Torus(cells_width as i32, cells_height as i32),
+            _ => Space::Bounded(cells_width as i32, cells_height as i32),
+        };
+        let mut cells = HashSet::new();
+        for y in 0..cells_height {
+            for x in 0..cells_width {
+                if is_alive(x, y) {
+                    cells.insert((x as i32, y as i32));
+                }
+            }
+        }
+
+        // Cells of objects may be dead in the current generation, such as for blinkers:
+        let mut cells_in_period = cells.clone();
+        let mut generation_cells = cells.clone();
+        for _ in 1..period.clamp(1, MAX_PERIOD) {
+            generation_cells = step(space, rule, &generation_cells);
+            cells_in_period.extend(&generation_cells);
+        }
+
+        let mut counts: HashMap<(String, ObjectKind, u64), u32> = HashMap::new();
+        for group in components(space, &cells_in_period, 2) {
+            let group_cells_in_period: HashSet<Cell> = group
+                .iter()
+                .map(|&(_, unwrapped_cell)| unwrapped_cell)
+                .collect();
+            let group_cells: HashSet<Cell> = group
+                .iter()
+                .filter(|(cell, _)| cells.contains(cell))
+                .map(|&(_, unwrapped_cell)| unwrapped_cell)
+                .collect();
+            for object in objects(rule, &group_cells_in_period, &group_cells) {
+                *counts.entry(object).or_default() += 1;
+            }
+        }
+
+        let mut entries: Vec<CensusEntry> = counts
+            .into_iter()
+            .map(|((apgcode, kind, period), count)| CensusEntry {
+                apgcode,
+                kind,
+                period,
+                count,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.apgcode.cmp(&b.apgcode))
+        });
+        Self { entries }
+    }
+
+    /// The census as JSON, such as
+    /// `{"objects":[{"apgcode":"xs4_33","kind":"still life","period":1,"count":3}]}`.
+    pub fn to_json(&self) -> String {
+        let objects: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    r#"{{"apgcode":"{}","kind":"{}","period":{},"count":{}}}"#,
+                    entry.apgcode,
+                    entry.kind.name(),
+                    entry.period,
+                    entry.count
+                )
+            })
+            .collect();
+        format!(r#"{{"objects":[{}]}}"#, objects.join(","))
+    }
+}
+
+/// A table with a line for each apgcode.
+impl fmt::Display for Census {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let apgcode_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.apgcode.len())
+            .max()
+            .unwrap_or_default();
+        for entry in &self.entries {
+            write!(
+                f,
+                "\n{:>8}  {:apgcode_width$}  {}",
+                entry.count,
+                entry.apgcode,
+                entry.kind.name()
+            )?;
+            if entry.period > 1 {
+                write!(f, " (period {})", entry.period)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Space {
+    /// An infinite plane, for objects on their own.
+    Unbounded,
+    /// The grid with opposite edges joined.
+    Torus(i32, i32),
+    /// The grid with cells outside it always dead.
+    Bounded(i32, i32),
+}
+
+impl Space {
+    /// The cell at a position, if it is inside the space.
+    fn place(self, (x, y): Cell) -> Option<Cell> {
+        match self {
+            Self::Unbounded => Some((x, y)),
+            Self::Torus(width, height) => Some((x.rem_euclid(width), y.rem_euclid(height))),
+            Self::Bounded(width, height) => {
+                ((0..width).contains(&x) && (0..height).contains(&y)).then_some((x, y))
+            }
+        }
+    }
+
+    /// The cells at most `reach` cells away, along with their unwrapped positions.
+    fn neighbours(self, (x, y): Cell, reach: i32) -> impl Iterator<Item = (Cell, Cell)> {
+        (-reach..=reach)
+            .flat_map(move |dy| (-reach..=reach).map(move |dx| (x + dx, y + dy)))
+            .filter(move |&position| position != (x, y))
+            .filter_map(move |position| Some((self.place(position)?, position)))
+    }
+}
+
+fn step(space: Space, rule: &Rule, cells: &HashSet<Cell>) -> HashSet<Cell> {
+    let mut neighbour_counts: HashMap<Cell, u32> = HashMap::new();
+    for &cell in cells {
+        for (neighbour, _) in space.neighbours(cell, 1) {
+            *neighbour_counts.entry(neighbour).or_default() += 1;
+        }
+    }
+    neighbour_counts
+        .into_iter()
+        .filter(|(cell, count)| {
+            let mask = if cells.contains(cell) {
+                rule.survives
+            } else {
+                rule.born
+            };
+            mask & (1 << count) != 0
+        })
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+/// The groups of cells connected through cells at most `reach` cells apart, as pairs of
+/// cells in the space and their positions unwrapped to keep objects crossing the edges of
+/// a torus together.
+fn components(space: Space, cells: &HashSet<Cell>, reach: i32) -> Vec<Vec<(Cell, Cell)>> {
+    let mut visited = HashSet::new();
+    let mut components = Vec::new();
+    for &start in cells {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut component = Vec::new();
+        let mut to_visit = vec![(start, start)];
+        while let Some((cell, unwrapped_cell)) = to_visit.pop() {
+            component.push((cell, unwrapped_cell));
+            for (neighbour, unwrapped_neighbour) in space.neighbours(unwrapped_cell, reach) {
+                if cells.contains(&neighbour) && visited.insert(neighbour) {
+                    to_visit.push((neighbour, unwrapped_neighbour));
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// The apgcode, kind and period of the objects in a group of cells, which is split into
+/// its 8-connected parts if they are objects that do not interact with each other.
+fn objects(
+    rule: &Rule,
+    cells_in_period: &HashSet<Cell>,
+    cells: &HashSet<Cell>,
+) -> Vec<(String, ObjectKind, u64)> {
+    let parts: Vec<HashSet<Cell>> = components(Space::Unbounded, cells_in_period, 1)
+        .into_iter()
+        .map(|part| {
+            part.into_iter()
+                .map(|(cell, _)| cell)
+                .filter(|cell| cells.contains(cell))
+                .collect::<HashSet<_>>()
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.len() > 1 {
+        let classified: Vec<_> = parts.iter().map(|part| classify(rule, part)).collect();
+        let max_period = classified.iter().map(|&(_, _, period)| period).max();
+        if classified
+            .iter()
+            .all(|&(_, kind, _)| kind != ObjectKind::Unknown)
+            && evolve_independently(rule, &parts, max_period.unwrap_or_default() + 1)
+        {
+            return classified;
+        }
+    }
+    if cells.is_empty() {
+        Vec::new()
+    } else {
+        vec![classify(rule, cells)]
+    }
+}
+
+/// Whether evolving the parts together is the same as evolving each of them on its own.
+fn evolve_independently(rule: &Rule, parts: &[HashSet<Cell>], generations: u64) -> bool {
+    let mut together: HashSet<Cell> = parts.iter().flatten().copied().collect();
+    let mut separately = parts.to_vec();
+    for _ in 0..generations {
+        together = step(Space::Unbounded, rule, &together);
+        for part in &mut separately {
+            *part = step(Space::Unbounded, rule, part);
+        }
+        if separately.iter().flatten().copied().collect::<HashSet<_>>() != together {
+            return false;
+        }
+    }
+    true
+}
+
+/// The cells moved to start at `(0, 0)` and sorted, along with the offset they were moved by.
+fn normalize(cells: impl IntoIterator<Item = Cell>) -> (Vec<Cell>, Cell) {
+    let mut cells: Vec<Cell> = cells.into_iter().collect();
+    let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or_default();
+    let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or_default();
+    for cell in &mut cells {
+        *cell = (cell.0 - min_x, cell.1 - min_y);
+    }
+    cells.sort_unstable();
+    (cells, (min_x, min_y))
+}
+
+/// The apgcode, kind and period of an object evolved on its own.
+fn classify(rule: &Rule, object_cells: &HashSet<Cell>) -> (String, ObjectKind, u64) {
+    let (start, start_offset) = normalize(object_cells.iter().copied());
+    let mut phases = vec![start.clone()];
+    let mut cells = object_cells.clone();
+    for generation in 1..=MAX_PERIOD {
+        cells = step(Space::Unbounded, rule, &cells);
+        if cells.is_empty() || cells.len() > MAX_POPULATION {
+            break;
+        }
+        let (phase, offset) = normalize(cells.iter().copied());
+        if phase == start {
+            let code = canonical_code(&phases);
+            return if offset != start_offset {
+                (
+                    format!("xq{generation}_{code}"),
+                    ObjectKind::Spaceship,
+                    generation,
+                )
+            } else if generation == 1 {
+                (
+                    format!("xs{}_{code}", start.len()),
+                    ObjectKind::StillLife,
+                    generation,
+                )
+            } else {
+                (
+                    format!("xp{generation}_{code}"),
+                    ObjectKind::Oscillator,
+                    generation,
+                )
+            };
+        }
+        phases.push(phase);
+    }
+    (
+        format!("xx{}_{}", start.len(), canonical_code(&phases[..1])),
+        ObjectKind::Unknown,
+        0,
+    )
+}
+
+/// The shortest, and then alphabetically first, code of any phase in any orientation.
+fn canonical_code(phases: &[Vec<Cell>]) -> String {
+    let orientations: [fn(Cell) -> Cell; 8] = [
+        |(x, y)| (x, y),
+        |(x, y)| (-x, y),
+        |(x, y)| (x, -y),
+        |(x, y)| (-x, -y),
+        |(x, y)| (y, x),
+        |(x, y)| (-y, x),
+        |(x, y)| (y, -x),
+        |(x, y)| (-y, -x),
+    ];
+    phases
+        .iter()
+        .flat_map(|phase| {
+            orientations.iter().map(move |orient| {
+                extended_wechsler(&normalize(phase.iter().map(|&cell| orient(cell))).0)
+            })
+        })
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default()
+}
+
+/// Encode cells starting at `(0, 0)` in the
+/// [Extended Wechsler format](https://conwaylife.com/wiki/Apgcode#Extended_Wechsler_format),
+/// with a character for each column of strips of 5 rows and the strips separated by `z`.
+fn extended_wechsler(cells: &[Cell]) -> String {
+    let width = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or_default() as usize;
+    let height = cells.iter().map(|&(_, y)| y + 1).max().unwrap_or_default() as usize;
+    let mut columns = vec![0_usize; height.div_ceil(5) * width];
+    for &(x, y) in cells {
+        columns[y as usize / 5 * width + x as usize] |= 1 << (y % 5);
+    }
+
+    let mut code = String::new();
+    for (strip_idx, strip) in columns.chunks(width.max(1)).enumerate() {
+        if strip_idx > 0 {
+            code.push('z');
+        }
+        // Runs of empty columns are shortened, and left out at the end of a strip:
+        let mut zeros = 0;
+        for &column in strip {
+            if column == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros > 0 {
+                let run = zeros.min(39);
+                match run {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        code.push('y');
+                        code.push(char::from(DIGITS[run - 4]));
+                    }
+                }
+                zeros -= run;
+            }
+            code.push(char::from(DIGITS[column]));
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RULES;
+
+    /// Place patterns on a 40x40 torus, given as rows of `.` and `o` with the first row
+    /// at the bottom.
+    fn grid(patterns: &[(u32, u32, &[&str])]) -> HashSet<(u32, u32)> {
+        let mut cells = HashSet::new();
+        for &(x, y, rows) in patterns {
+            for (dy, row) in rows.iter().enumerate() {
+                for (dx, c) in row.chars().enumerate() {
+                    if c == 'o' {
+                        cells.insert(((x + dx as u32) % 40, (y + dy as u32) % 40));
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn test_census() {
+        let cells = grid(&[
+            (2, 2, &["oo", "oo"]),
+            (10, 2, &["oo", "oo"]),
+            (20, 2, &["ooo"]),
+            (2, 10, &[".o.", "o.o", "o.o", ".o."]),
+            (10, 10, &["ooo", "..o", ".o."]),
+            (20, 10, &["o..o.", "....o", "o...o", ".oooo"]),
+            // Crossing the edges of the torus:
+            (39, 39, &["oo", "oo"]),
+            // Not interacting, despite being close:
+            (30, 20, &["oo.oo", "oo.oo"]),
+        ]);
+        let census = Census::take(&RULES[0], Topology::Torus, 40, 40, 4, |x, y| {
+            cells.contains(&(x, y))
+        });
+        let entry = |apgcode: &str, kind, period, count| CensusEntry {
+            apgcode: apgcode.to_string(),
+            kind,
+            period,
+            count,
+        };
+        assert_eq!(
+            census.entries,
+            [
+                entry("xs4_33", ObjectKind::StillLife, 1, 5),
+                entry("xp2_7", ObjectKind::Oscillator, 2, 1),
+                entry("xq4_153", ObjectKind::Spaceship, 4, 1),
+                entry("xq4_6frc", ObjectKind::Spaceship, 4, 1),
+                entry("xs6_696", ObjectKind::StillLife, 1, 1),
+            ]
+        );
+        assert!(census.to_json().starts_with(
+            r#"{"objects":[{"apgcode":"xs4_33","kind":"still life","period":1,"count":5},"#
+        ));
+    }
+
+    #[test]
+    fn test_extended_wechsler() {
+        assert_eq!(extended_wechsler(&[(0, 0), (45, 0)]), "1yzy11");
+        assert_eq!(extended_wechsler(&[(0, 0), (1, 11)]), "1zz02");
+    }
+}
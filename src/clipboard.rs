@@ -0,0 +1,36 @@
+#[cfg(not(any(target_os = "android", target_family = "wasm")))]
+pub(crate) fn set_text(text: &str) {
+    use std::sync::Mutex;
+
+    // The clipboard is kept alive, as on some platforms (such as X11) the
+    // clipboard contents are lost when the owning `arboard::Clipboard` is dropped.
+    static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+    let Ok(mut clipboard) = CLIPBOARD.lock() else {
+        return;
+    };
+    if clipboard.is_none() {
+        match arboard::Clipboard::new() {
+            Ok(new_clipboard) => *clipboard = Some(new_clipboard),
+            Err(e) => {
+                log::error!("Failed opening clipboard: {e}");
+                return;
+            }
+        }
+    }
+    if let Some(clipboard) = clipboard.as_mut()
+        && let Err(e) = clipboard.set_text(text)
+    {
+        log::error!("Failed setting clipboard text: {e}");
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub(crate) fn set_text(text: &str) {
+    crate::web::copy_to_clipboard(text);
+}
+
+#[cfg(target_os = "android")]
+pub(crate) fn set_text(text: &str) {
+    log::warn!("Clipboard not supported, copied text:\n{text}");
+}
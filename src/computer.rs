@@ -1,15 +1,298 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
 use crate::rules::Rule;
+use crate::selection::Rect;
+use crate::topology::Topology;
+use crate::transform::{Transform, Transformer};
+
+/// The width and height of the compute shader workgroups.
+pub(crate) const WORKGROUP_WIDTH: u32 = 8;
+
+/// How cells are stored in the cell buffers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellStorage {
+    /// One `u32` per cell, holding the age of live cells which the renderer shows as colors.
+    #[default]
+    Ages,
+    /// 32 cells per `u32`, for larger grids and faster computation without tracking ages.
+    ///
+    /// Bit `x % 32` of word `y * words_per_row + x / 32` holds the cell `(x, y)`.
+    Bits,
+}
+
+impl CellStorage {
+    /// The number of `u32` words holding a row of cells.
+    pub const fn words_per_row(self, cells_width: u32) -> u32 {
+        match self {
+            Self::Ages => cells_width,
+            Self::Bits => cells_width.div_ceil(32),
+        }
+    }
+
+    /// The size in bytes of a buffer holding a grid of cells.
+    pub fn buffer_size(self, cells_width: u32, cells_height: u32) -> u64 {
+        u64::from(self.words_per_row(cells_width))
+            * u64::from(cells_height)
+            * std::mem::size_of::<u32>() as u64
+    }
+}
+
+/// The number of generations advanced by a dispatch of the multi-generation kernel,
+/// see [`Computer::enqueue_generations()`].
+pub const GENERATIONS_PER_DISPATCH: u32 = 4;
+
+/// How the compute shader for [`CellStorage::Ages`] reads the neighbours of cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kernel {
+    /// Each cell reads its neighbours from the cell buffer.
+    Direct,
+    /// Each workgroup first loads its cells and their neighbours into shared memory.
+    #[default]
+    Tiled,
+}
+
+/// Check that a grid of the given size can be computed within the limits of a device.
+pub fn validate_size(
+    limits: &wgpu::Limits,
+    cells_width: u32,
+    cells_height: u32,
+    storage: CellStorage,
+) -> Result<(), String> {
+    if cells_width == 0 || cells_height == 0 {
+        return Err(format!("Invalid grid size {cells_width}x{cells_height}"));
+    }
+    let buffer_size = storage.buffer_size(cells_width, cells_height);
+    let max_buffer_size = limits
+        .max_buffer_size
+        .min(limits.max_storage_buffer_binding_size);
+    if buffer_size > max_buffer_size {
+        return Err(format!(
+            "Grid size {cells_width}x{cells_height} needs {buffer_size} bytes, more than the device limit of {max_buffer_size} bytes"
+        ));
+    }
+    let max_workgroups = limits.max_compute_workgroups_per_dimension;
+    if storage.words_per_row(cells_width).div_ceil(WORKGROUP_WIDTH) > max_workgroups
+        || cells_height.div_ceil(WORKGROUP_WIDTH) > max_workgroups
+    {
+        return Err(format!(
+            "Grid size {cells_width}x{cells_height} needs more than the device limit of {max_workgroups} workgroups per dimension"
+        ));
+    }
+    Ok(())
+}
+
+/// Counts of cells in a generation, see [`Computer::statistics()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The number of live cells.
+    pub population: u32,
+    /// The number of cells alive that were dead in the previous generation.
+    pub births: u32,
+    /// The number of cells dead that were alive in the previous generation.
+    pub deaths: u32,
+    /// A hash of the positions of live cells, equal for generations with the same cells.
+    pub hash: u64,
+}
+
+/// Cells read back from the GPU.
+pub struct Cells {
+    cells_width: u32,
+    storage: CellStorage,
+    words: Vec<u32>,
+}
+
+impl Cells {
+    pub(crate) const fn new(cells_width: u32, storage: CellStorage, words: Vec<u32>) -> Self {
+        Self {
+            cells_width,
+            storage,
+            words,
+        }
+    }
+
+    /// The age of the cell at `(x, y)` if alive, which is always 1 for bit-packed cells, or else 0.
+    pub fn get(&self, x: u32, y: u32) -> u32 {
+        let row = y as usize * self.storage.words_per_row(self.cells_width) as usize;
+        match self.storage {
+            CellStorage::Ages => self.words[row + x as usize],
+            CellStorage::Bits => (self.words[row + x as usize / 32] >> (x % 32)) & 1,
+        }
+    }
+
+    /// The cells as stored in the cell buffers.
+    pub fn into_words(self) -> Vec<u32> {
+        self.words
+    }
+}
+
+/// A value read back from the GPU, shared between the callback receiving it and the
+/// future awaiting it.
+struct Readback<T> {
+    value: Option<T>,
+    /// Whether the callback has been called, or dropped without a value as when reading fails.
+    done: bool,
+    waker: Option<Waker>,
+}
+
+/// Moved into the callback of a readback, completing the [`ReadbackFuture`] when dropped.
+struct ReadbackSender<T>(Arc<Mutex<Readback<T>>>);
+
+impl<T> ReadbackSender<T> {
+    fn send(self, value: T) {
+        if let Ok(mut readback) = self.0.lock() {
+            readback.value = Some(value);
+        }
+    }
+}
+
+impl<T> Drop for ReadbackSender<T> {
+    fn drop(&mut self) {
+        if let Ok(mut readback) = self.0.lock() {
+            readback.done = true;
+            if let Some(waker) = readback.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Resolves to the value sent by the [`ReadbackSender`], or `None` if it was dropped unsent.
+struct ReadbackFuture<T>(Arc<Mutex<Readback<T>>>);
+
+impl<T> Future for ReadbackFuture<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let Ok(mut readback) = self.0.lock() else {
+            return Poll::Ready(None);
+        };
+        if readback.done {
+            Poll::Ready(readback.value.take())
+        } else {
+            readback.waker = Some(context.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+fn readback<T>() -> (ReadbackSender<T>, ReadbackFuture<T>) {
+    let readback = Arc::new(Mutex::new(Readback {
+        value: None,
+        done: false,
+        waker: None,
+    }));
+    (
+        ReadbackSender(Arc::clone(&readback)),
+        ReadbackFuture(readback),
+    )
+}
+
+/// The cells of a grid randomized from a seed, laid out as stored in the cell buffers.
+pub(crate) fn random_words(
+    storage: CellStorage,
+    cells_width: u32,
+    cells_height: u32,
+    seed: u32,
+    initial_density: u8,
+) -> Vec<u32> {
+    use rand::RngExt as _;
+    use rand::prelude::SeedableRng;
+
+    let words_per_row = storage.words_per_row(cells_width) as usize;
+    let mut words = vec![0_u32; words_per_row * cells_height as usize];
+    let initial_density = f32::from(initial_density) * 0.01;
+    let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(u64::from(seed));
+    for y in 0..cells_height as usize {
+        for x in 0..cells_width as usize {
+            if rng.random::<f32>() < initial_density {
+                match storage {
+                    CellStorage::Ages => words[y * words_per_row + x] = 1,
+                    CellStorage::Bits => words[y * words_per_row + x / 32] |= 1 << (x % 32),
+                }
+            }
+        }
+    }
+    words
+}
+
+/// The hash of a live cell at an index counted in rows of cells, as in
+/// `game-of-life.statistics.wgsl`, split in two halves that are summed over live cells.
+pub(crate) const fn cell_hash(index: u32) -> [u32; 2] {
+    const fn hash(value: u32) -> u32 {
+        let mut h = value;
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7feb_352d);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x846c_a68b);
+        h ^= h >> 16;
+        h
+    }
+    let low = hash(index);
+    [low, hash(low ^ 0x9e37_79b9)]
+}
+
+/// Check that a rectangle of cells is inside a grid, with a value for each cell.
+pub(crate) fn validate_rect(
+    rect: Rect,
+    values: &[u32],
+    cells_width: u32,
+    cells_height: u32,
+) -> Result<(), String> {
+    if u64::from(rect.x) + u64::from(rect.width) > u64::from(cells_width)
+        || u64::from(rect.y) + u64::from(rect.height) > u64::from(cells_height)
+    {
+        return Err(format!(
+            "{rect:?} is outside the grid of size {cells_width}x{cells_height}"
+        ));
+    }
+    if values.len() != rect.width as usize * rect.height as usize {
+        return Err(format!(
+            "Expected {} values for {rect:?}, got {}",
+            rect.width as usize * rect.height as usize,
+            values.len()
+        ));
+    }
+    Ok(())
+}
 
 pub struct ComputerFactory {
     shader: wgpu::ShaderModule,
+    bits_shader: wgpu::ShaderModule,
     bind_group_layout: wgpu::BindGroupLayout,
-    pub(crate) size_buffer: wgpu::Buffer,
-    pub(crate) rule_buffer: wgpu::Buffer,
+    write_bind_group_layout: wgpu::BindGroupLayout,
+    write_pipeline: wgpu::ComputePipeline,
+    statistics_bind_group_layout: wgpu::BindGroupLayout,
+    statistics_pipeline: wgpu::ComputePipeline,
+    statistics_bits_pipeline: wgpu::ComputePipeline,
+    kernel: Kernel,
 }
 
 impl ComputerFactory {
     pub fn new(device: &wgpu::Device) -> Self {
-        let shader = device.create_shader_module(wgpu::include_wgsl!("game-of-life.compute.wgsl"));
+        // The shaders share the topology handling:
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("game-of-life.compute.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("game-of-life.topology.wgsl"),
+                    include_str!("game-of-life.compute.wgsl")
+                )
+                .into(),
+            ),
+        });
+        let bits_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("game-of-life.compute-bits.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("game-of-life.topology.wgsl"),
+                    include_str!("game-of-life.compute-bits.wgsl")
+                )
+                .into(),
+            ),
+        });
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
@@ -52,77 +335,274 @@ impl ComputerFactory {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
             label: Some("compute_bind_group_layout"),
         });
 
-        let size_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("size_buffer"),
-            size: (2 * std::mem::size_of::<u32>()) as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        let write_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("write_bind_group_layout"),
+            });
+        let write_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("write_pipeline_layout"),
+                bind_group_layouts: &[None, Some(&write_bind_group_layout)],
+                immediate_size: 0,
+            });
+        let write_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("write_pipeline"),
+            layout: Some(&write_pipeline_layout),
+            module: &bits_shader,
+            entry_point: Some("write_rect"),
+            compilation_options: Default::default(),
+            cache: Default::default(),
         });
 
-        let rule_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("rule_buffer"),
-            size: (2 * std::mem::size_of::<u32>()) as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        let statistics_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("game-of-life.statistics.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("game-of-life.statistics.wgsl").into()),
         });
+        let statistics_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("statistics_bind_group_layout"),
+            });
+        let statistics_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("statistics_pipeline_layout"),
+                bind_group_layouts: &[Some(&statistics_bind_group_layout)],
+                immediate_size: 0,
+            });
+        let create_statistics_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("statistics_pipeline"),
+                layout: Some(&statistics_pipeline_layout),
+                module: &statistics_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: Default::default(),
+            })
+        };
+        let statistics_pipeline = create_statistics_pipeline("main");
+        let statistics_bits_pipeline = create_statistics_pipeline("main_bits");
 
         Self {
             shader,
+            bits_shader,
             bind_group_layout,
-            size_buffer,
-            rule_buffer,
+            write_bind_group_layout,
+            write_pipeline,
+            statistics_bind_group_layout,
+            statistics_pipeline,
+            statistics_bits_pipeline,
+            kernel: Kernel::default(),
         }
     }
 
+    /// Use the given kernel for computers created with cell ages.
+    #[must_use]
+    pub fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn create(
         &self,
         device: &wgpu::Device,
         cells_width: u32,
         cells_height: u32,
+        storage: CellStorage,
         rule: &Rule,
+        topology: Topology,
         seed: u32,
         initial_density: u8,
-        queue: &wgpu::Queue,
-    ) -> Computer {
-        use rand::RngExt as _;
-        use rand::prelude::SeedableRng;
-        use wgpu::util::DeviceExt;
-
-        let size_array = [cells_width, cells_height];
-        queue.write_buffer(&self.size_buffer, 0, bytemuck::cast_slice(&size_array));
+    ) -> Result<Computer, String> {
+        self.create_batch(
+            device,
+            cells_width,
+            cells_height,
+            storage,
+            rule,
+            topology,
+            &[seed],
+            initial_density,
+        )
+    }
 
-        queue.write_buffer(
-            &self.rule_buffer,
-            0,
-            bytemuck::cast_slice(&rule.rule_array()),
-        );
+    /// Create a computer advancing a batch of independent grids of the same size at once,
+    /// with a grid randomized from each of the `seeds`.
+    ///
+    /// The grids are stored one after the other in the cell buffers, and each dispatch
+    /// computes all of them, which is much faster than a computer for each of many small grids.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_batch(
+        &self,
+        device: &wgpu::Device,
+        cells_width: u32,
+        cells_height: u32,
+        storage: CellStorage,
+        rule: &Rule,
+        topology: Topology,
+        seeds: &[u32],
+        initial_density: u8,
+    ) -> Result<Computer, String> {
+        use wgpu::util::DeviceExt;
 
-        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(u64::from(seed));
-        let mut cells_vec = vec![0_u32; cells_width as usize * cells_height as usize];
-        let initial_density = f32::from(initial_density) * 0.01;
-        for cell in cells_vec.iter_mut() {
-            if rng.random::<f32>() < initial_density {
-                *cell = 1;
-            }
+        let limits = device.limits();
+        validate_size(&limits, cells_width, cells_height, storage)?;
+        if !topology.supports_size(cells_width, cells_height) {
+            return Err(format!(
+                "{} does not support grid size {cells_width}x{cells_height}",
+                topology.name()
+            ));
+        }
+        let batch = u32::try_from(seeds.len())
+            .ok()
+            .filter(|&batch| batch > 0 && batch <= limits.max_compute_workgroups_per_dimension)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid batch of {} grids, the device supports 1 to {}",
+                    seeds.len(),
+                    limits.max_compute_workgroups_per_dimension
+                )
+            })?;
+        let batch_buffer_size = storage.buffer_size(cells_width, cells_height) * u64::from(batch);
+        let max_buffer_size = limits
+            .max_buffer_size
+            .min(limits.max_storage_buffer_binding_size);
+        if batch_buffer_size > max_buffer_size {
+            return Err(format!(
+                "A batch of {batch} grids of size {cells_width}x{cells_height} needs {batch_buffer_size} bytes, more than the device limit of {max_buffer_size} bytes"
+            ));
         }
 
-        let cells_buffer_usages = {
-            #[cfg(not(test))]
-            {
-                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX
-            }
-            #[cfg(test)]
-            {
-                wgpu::BufferUsages::STORAGE
-                    | wgpu::BufferUsages::VERTEX
-                    | wgpu::BufferUsages::COPY_SRC
-            }
+        // Each computer has its own uniforms, so that creating one leaves others running:
+        let uniform_buffer = |label, contents: &[u32]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(contents),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
         };
+        let size_buffer = uniform_buffer("size_buffer", &[cells_width, cells_height]);
+        let rule_buffer = uniform_buffer("rule_buffer", &rule.rule_array());
+        let topology_buffer = uniform_buffer("topology_buffer", &topology.uniform_array());
+
+        let cells_vec: Vec<u32> = seeds
+            .iter()
+            .flat_map(|&seed| {
+                random_words(storage, cells_width, cells_height, seed, initial_density)
+            })
+            .collect();
+
+        let cells_buffer_usages = wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::VERTEX
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC;
 
         let cells_buffer_0 = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
@@ -158,7 +638,7 @@ impl ComputerFactory {
                 wgpu::BindGroupEntry {
                     binding: 2,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &self.size_buffer,
+                        buffer: &size_buffer,
                         offset: 0,
                         size: None,
                     }),
@@ -166,7 +646,15 @@ impl ComputerFactory {
                 wgpu::BindGroupEntry {
                     binding: 3,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &self.rule_buffer,
+                        buffer: &rule_buffer,
+                        offset: 0,
+                        size: None,
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &topology_buffer,
                         offset: 0,
                         size: None,
                     }),
@@ -195,41 +683,629 @@ impl ComputerFactory {
         let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("compute_pipeline"),
             layout: Some(&compute_pipeline_layout),
-            module: &self.shader,
-            entry_point: Some("main"),
+            module: match storage {
+                CellStorage::Ages => &self.shader,
+                CellStorage::Bits => &self.bits_shader,
+            },
+            entry_point: Some(match (storage, self.kernel) {
+                (CellStorage::Ages, Kernel::Tiled) => "main_tiled",
+                _ => "main",
+            }),
             compilation_options: Default::default(),
             cache: Default::default(),
         });
 
-        Computer {
+        // The multi-generation kernel maps cells several steps outside the grid, which is only
+        // done consistently for these topologies and grids at least as large as its tiles:
+        let multi_pipeline = (storage == CellStorage::Ages
+            && matches!(
+                topology,
+                Topology::Torus | Topology::Plane | Topology::KleinBottle { .. }
+            )
+            && cells_width.min(cells_height) >= WORKGROUP_WIDTH + 2 * GENERATIONS_PER_DISPATCH)
+            .then(|| {
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("multi_compute_pipeline"),
+                    layout: Some(&compute_pipeline_layout),
+                    module: &self.shader,
+                    entry_point: Some("main_multi"),
+                    compilation_options: Default::default(),
+                    cache: Default::default(),
+                })
+            });
+
+        let statistics_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("statistics_buffer"),
+            size: (std::mem::size_of::<[u32; 5]>() * seeds.len()) as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let create_statistics_bind_group =
+            |current_buffer: &wgpu::Buffer, previous_buffer: &wgpu::Buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.statistics_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: current_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: previous_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: statistics_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: size_buffer.as_entire_binding(),
+                        },
+                    ],
+                    label: Some("statistics_bind_group"),
+                })
+            };
+        let statistics_bind_group_of_0 =
+            create_statistics_bind_group(&cells_buffer_0, &cells_buffer_1);
+        let statistics_bind_group_of_1 =
+            create_statistics_bind_group(&cells_buffer_1, &cells_buffer_0);
+
+        Ok(Computer {
             cells_width,
             cells_height,
+            batch,
+            storage,
             compute_pipeline,
+            multi_pipeline,
+            write_bind_group_layout: self.write_bind_group_layout.clone(),
+            write_pipeline: self.write_pipeline.clone(),
+            statistics_pipeline: match storage {
+                CellStorage::Ages => self.statistics_pipeline.clone(),
+                CellStorage::Bits => self.statistics_bits_pipeline.clone(),
+            },
+            statistics_bind_group_of_0,
+            statistics_bind_group_of_1,
+            statistics_buffer,
             currently_computed_is_0: true,
+            size_buffer,
             compute_bind_group_from_0_to_1,
             compute_bind_group_from_1_to_0,
             cells_buffer_0,
             cells_buffer_1,
-        }
+        })
     }
 }
 
 pub struct Computer {
     cells_width: u32,
     cells_height: u32,
+    batch: u32,
+    storage: CellStorage,
     compute_pipeline: wgpu::ComputePipeline,
+    multi_pipeline: Option<wgpu::ComputePipeline>,
+    write_bind_group_layout: wgpu::BindGroupLayout,
+    write_pipeline: wgpu::ComputePipeline,
+    statistics_pipeline: wgpu::ComputePipeline,
+    /// Comparing `cells_buffer_0` as the current generation to `cells_buffer_1` as the previous.
+    statistics_bind_group_of_0: wgpu::BindGroup,
+    statistics_bind_group_of_1: wgpu::BindGroup,
+    statistics_buffer: wgpu::Buffer,
     compute_bind_group_from_0_to_1: wgpu::BindGroup,
     compute_bind_group_from_1_to_0: wgpu::BindGroup,
     pub currently_computed_is_0: bool,
+    /// The width and height of the grid, also bound by the renderer.
+    pub(crate) size_buffer: wgpu::Buffer,
     pub cells_buffer_0: wgpu::Buffer,
     pub cells_buffer_1: wgpu::Buffer,
 }
 
 impl Computer {
+    pub const fn storage(&self) -> CellStorage {
+        self.storage
+    }
+
+    /// The number of grids advanced together, see [`ComputerFactory::create_batch()`].
+    ///
+    /// Only the first grid is rendered, edited and transformed, which leaves the other grids
+    /// of a transformed batch out of date, so those are meant for computers of a single grid.
+    pub const fn batch(&self) -> u32 {
+        self.batch
+    }
+
+    /// The buffer holding the current generation, which the next `enqueue` reads from.
+    pub fn current_cells_buffer(&self) -> &wgpu::Buffer {
+        if self.currently_computed_is_0 {
+            &self.cells_buffer_0
+        } else {
+            &self.cells_buffer_1
+        }
+    }
+
+    /// Apply a transform keeping the grid size, such as a translation or a rotation of a square grid.
+    ///
+    /// Fails without changing the cells if the transform would change the grid size.
+    pub fn transform(
+        &mut self,
+        transformer: &Transformer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        transform: &Transform,
+    ) -> Result<(), String> {
+        let size = (self.cells_width, self.cells_height);
+        let destination_size = transform.destination_size(size.0, size.1);
+        if destination_size != size {
+            return Err(format!(
+                "The transform changes the grid size from {}x{} to {}x{}",
+                size.0, size.1, destination_size.0, destination_size.1
+            ));
+        }
+
+        let (source, destination) = if self.currently_computed_is_0 {
+            (&self.cells_buffer_0, &self.cells_buffer_1)
+        } else {
+            (&self.cells_buffer_1, &self.cells_buffer_0)
+        };
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("transform_encoder"),
+        });
+        transformer.enqueue(
+            device,
+            &mut encoder,
+            transform,
+            self.storage,
+            source,
+            size,
+            destination,
+            size,
+        )?;
+        queue.submit(std::iter::once(encoder.finish()));
+        self.currently_computed_is_0 = !self.currently_computed_is_0;
+        Ok(())
+    }
+
+    /// Count and hash the cells of the current generation on the GPU, calling `on_read`
+    /// with the counts once read back.
+    ///
+    /// Births and deaths are counted compared to the previous generation, or to the
+    /// cells before they were last edited or transformed.
+    ///
+    /// Unlike [`Self::read_cells()`] this does not wait for the GPU, so `on_read` is
+    /// called once the device has been polled, such as when submitting later work.
+    pub fn statistics(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        on_read: impl FnOnce(Statistics) + wgpu::WasmNotSend + 'static,
+    ) {
+        self.batch_statistics(device, queue, move |statistics| {
+            if let Some(&statistics) = statistics.first() {
+                on_read(statistics);
+            }
+        });
+    }
+
+    /// Like [`Self::statistics()`], but for each grid of the batch.
+    pub fn batch_statistics(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        on_read: impl FnOnce(Vec<Statistics>) + wgpu::WasmNotSend + 'static,
+    ) {
+        /// Enough invocations to keep the GPU busy, each striding over many cells in large grids.
+        const MAX_WORKGROUPS: u32 = 1024;
+
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("statistics_read_buffer"),
+            size: self.statistics_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("statistics_encoder"),
+        });
+        encoder.clear_buffer(&self.statistics_buffer, 0, None);
+        {
+            let mut pass_encoder =
+                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass_encoder.set_pipeline(&self.statistics_pipeline);
+            pass_encoder.set_bind_group(
+                0,
+                if self.currently_computed_is_0 {
+                    &self.statistics_bind_group_of_0
+                } else {
+                    &self.statistics_bind_group_of_1
+                },
+                &[],
+            );
+            let words = self.storage.words_per_row(self.cells_width) * self.cells_height;
+            pass_encoder.dispatch_workgroups(
+                words.div_ceil(256).min(MAX_WORKGROUPS),
+                1,
+                self.batch,
+            );
+        }
+        encoder.copy_buffer_to_buffer(
+            &self.statistics_buffer,
+            0,
+            &read_buffer,
+            0,
+            read_buffer.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let read_buffer_in_callback = read_buffer.clone();
+        read_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Err(e) = result {
+                    log::error!("Failed reading statistics: {e}");
+                    return;
+                }
+                let statistics = bytemuck::cast_slice::<u8, [u32; 5]>(
+                    &read_buffer_in_callback.slice(..).get_mapped_range(),
+                )
+                .iter()
+                .map(
+                    |&[population, births, deaths, hash_low, hash_high]| Statistics {
+                        population,
+                        births,
+                        deaths,
+                        hash: u64::from(hash_high) << 32 | u64::from(hash_low),
+                    },
+                )
+                .collect();
+                read_buffer_in_callback.unmap();
+                on_read(statistics);
+            });
+    }
+
+    /// Read back the current generation, resolving to the cells once available.
+    ///
+    /// The cells are copied when called rather than when awaited. On native platforms this
+    /// waits for the GPU, so the future is ready on return, while on the web it resolves
+    /// once the browser has mapped the buffer.
+    pub fn read_cells(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<Cells, String>> + use<> {
+        let batch_cells = self.read_batch_cells(device, queue);
+        async move {
+            batch_cells
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| "Failed reading cells".to_string())
+        }
+    }
+
+    /// Like [`Self::read_cells()`], but for each grid of the batch.
+    pub fn read_batch_cells(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<Vec<Cells>, String>> + use<> {
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("read_buffer"),
+            size: self.cells_buffer_0.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("read_cells_encoder"),
+        });
+        encoder.copy_buffer_to_buffer(
+            self.current_cells_buffer(),
+            0,
+            &read_buffer,
+            0,
+            read_buffer.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, future) = readback();
+        let mapped_buffer = read_buffer.clone();
+        let (cells_width, storage) = (self.cells_width, self.storage);
+        let grid_words = storage.words_per_row(cells_width) as usize * self.cells_height as usize;
+        read_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Err(e) = result {
+                    log::error!("Failed reading cells: {e}");
+                    return;
+                }
+                let cells =
+                    bytemuck::cast_slice::<u8, u32>(&mapped_buffer.slice(..).get_mapped_range())
+                        .chunks_exact(grid_words)
+                        .map(|words| Cells {
+                            cells_width,
+                            storage,
+                            words: words.to_vec(),
+                        })
+                        .collect();
+                mapped_buffer.unmap();
+                sender.send(cells);
+            });
+
+        #[cfg(not(target_family = "wasm"))]
+        if let Err(e) = device.poll(wgpu::PollType::wait_indefinitely()) {
+            log::error!("Failed waiting for cells to be read: {e}");
+        }
+        async move {
+            future
+                .await
+                .ok_or_else(|| "Failed reading cells".to_string())
+        }
+    }
+
+    /// Count and hash the cells of the current generation, resolving to the counts once
+    /// read back, see [`Self::statistics()`].
+    pub async fn statistics_async(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Statistics, String> {
+        let (sender, future) = readback();
+        self.statistics(device, queue, move |statistics| sender.send(statistics));
+        #[cfg(not(target_family = "wasm"))]
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("Failed waiting for statistics: {e}"))?;
+        future
+            .await
+            .ok_or_else(|| "Failed reading statistics".to_string())
+    }
+
+    /// Replace all cells of the first grid with ones laid out as stored in the cell buffers.
+    pub(crate) fn write_words(&self, queue: &wgpu::Queue, words: &[u32]) {
+        debug_assert_eq!(
+            std::mem::size_of_val(words) as u64,
+            self.storage
+                .buffer_size(self.cells_width, self.cells_height)
+        );
+        queue.write_buffer(self.current_cells_buffer(), 0, bytemuck::cast_slice(words));
+    }
+
+    /// Set the cell at `(x, y)` of the current generation to 0 for a dead cell, or the age
+    /// of a live cell.
+    pub fn set_cell(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        x: u32,
+        y: u32,
+        value: u32,
+    ) -> Result<(), String> {
+        self.write_cells(
+            device,
+            queue,
+            Rect {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            },
+            &[value],
+        )
+    }
+
+    /// Write a rectangle of cells to the current generation, where `values` holds the
+    /// rows of the rectangle with 0 for dead cells and the age of live cells.
+    pub fn write_cells(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: Rect,
+        values: &[u32],
+    ) -> Result<(), String> {
+        validate_rect(rect, values, self.cells_width, self.cells_height)?;
+        if rect.width == 0 || rect.height == 0 {
+            return Ok(());
+        }
+
+        if self.storage == CellStorage::Ages {
+            for (row_offset, row) in values.chunks_exact(rect.width as usize).enumerate() {
+                let cell_index = (u64::from(rect.y) + row_offset as u64)
+                    * u64::from(self.cells_width)
+                    + u64::from(rect.x);
+                queue.write_buffer(
+                    self.current_cells_buffer(),
+                    cell_index * std::mem::size_of::<u32>() as u64,
+                    bytemuck::cast_slice(row),
+                );
+            }
+            return Ok(());
+        }
+
+        let rect_words_per_row = CellStorage::Bits.words_per_row(rect.width) as usize;
+        let mut rect_cells = vec![0_u32; rect_words_per_row * rect.height as usize];
+        for (index, &value) in values.iter().enumerate() {
+            if value > 0 {
+                let (x, y) = (index % rect.width as usize, index / rect.width as usize);
+                rect_cells[y * rect_words_per_row + x / 32] |= 1 << (x % 32);
+            }
+        }
+        let rect_mask = vec![u32::MAX; rect_cells.len()];
+        self.write_bits(device, queue, rect, &rect_cells, &rect_mask);
+        Ok(())
+    }
+
+    /// Set each of the given cells of the current generation to `value`, 0 for dead cells or
+    /// the age of live cells, such as the cells of a drawn line in a single submission.
+    pub fn set_cells(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cells: &[(u32, u32)],
+        value: u32,
+    ) -> Result<(), String> {
+        if let Some(&(x, y)) = cells
+            .iter()
+            .find(|&&(x, y)| x >= self.cells_width || y >= self.cells_height)
+        {
+            return Err(format!(
+                "Cell ({x}, {y}) is outside the {}x{} grid",
+                self.cells_width, self.cells_height
+            ));
+        }
+        let (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) = (
+            cells.iter().map(|&(x, _)| x).min(),
+            cells.iter().map(|&(_, y)| y).min(),
+            cells.iter().map(|&(x, _)| x).max(),
+            cells.iter().map(|&(_, y)| y).max(),
+        ) else {
+            return Ok(());
+        };
+
+        if self.storage == CellStorage::Ages {
+            // Written when the queue is next submitted, together with the other writes:
+            for &(x, y) in cells {
+                let cell_index = u64::from(y) * u64::from(self.cells_width) + u64::from(x);
+                queue.write_buffer(
+                    self.current_cells_buffer(),
+                    cell_index * std::mem::size_of::<u32>() as u64,
+                    bytemuck::cast_slice(&[value]),
+                );
+            }
+            return Ok(());
+        }
+
+        let rect = Rect {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        };
+        let rect_words_per_row = CellStorage::Bits.words_per_row(rect.width) as usize;
+        let mut rect_cells = vec![0_u32; rect_words_per_row * rect.height as usize];
+        let mut rect_mask = vec![0_u32; rect_cells.len()];
+        for &(x, y) in cells {
+            let (x, y) = ((x - rect.x) as usize, (y - rect.y) as usize);
+            let bit = 1 << (x % 32);
+            rect_mask[y * rect_words_per_row + x / 32] |= bit;
+            if value > 0 {
+                rect_cells[y * rect_words_per_row + x / 32] |= bit;
+            }
+        }
+        self.write_bits(device, queue, rect, &rect_cells, &rect_mask);
+        Ok(())
+    }
+
+    /// Merge bit-packed cells of a rectangle into the existing words on the GPU, as they
+    /// share words with cells outside the rectangle, writing the cells set in `rect_mask`.
+    fn write_bits(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: Rect,
+        rect_cells: &[u32],
+        rect_mask: &[u32],
+    ) {
+        use wgpu::util::DeviceExt;
+
+        let storage_buffer = |label, contents: &[u32]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(contents),
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
+        let rect_cells_buffer = storage_buffer("rect_cells_buffer", rect_cells);
+        let rect_mask_buffer = storage_buffer("rect_mask_buffer", rect_mask);
+        let rect_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("rect_buffer"),
+            contents: bytemuck::cast_slice(&[rect.x, rect.y, rect.width, rect.height]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("write_size_buffer"),
+            contents: bytemuck::cast_slice(&[self.cells_width, self.cells_height]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.write_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.current_cells_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: rect_cells_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: rect_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: size_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: rect_mask_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("write_bind_group"),
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("write_rect_encoder"),
+        });
+        {
+            let mut pass_encoder = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("write_rect_pass"),
+                timestamp_writes: None,
+            });
+            pass_encoder.set_pipeline(&self.write_pipeline);
+            pass_encoder.set_bind_group(1, &bind_group, &[]);
+            let word_columns = (rect.x + rect.width - 1) / 32 - rect.x / 32 + 1;
+            pass_encoder.dispatch_workgroups(
+                word_columns.div_ceil(WORKGROUP_WIDTH),
+                rect.height.div_ceil(WORKGROUP_WIDTH),
+                1,
+            );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Advance one generation.
     pub fn enqueue(&mut self, command_encoder: &mut wgpu::CommandEncoder) {
+        let pipeline = self.compute_pipeline.clone();
+        self.dispatch(command_encoder, &pipeline);
+    }
+
+    /// Advance the given number of generations, computing [`GENERATIONS_PER_DISPATCH`]
+    /// generations at a time where supported.
+    pub fn enqueue_generations(
+        &mut self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        mut generations: u64,
+    ) {
+        if let Some(multi_pipeline) = self.multi_pipeline.clone() {
+            // The last generation is computed by itself, so that the previous generation
+            // is kept to compare with in `statistics`:
+            while generations > u64::from(GENERATIONS_PER_DISPATCH) {
+                self.dispatch(command_encoder, &multi_pipeline);
+                generations -= u64::from(GENERATIONS_PER_DISPATCH);
+            }
+        }
+        for _ in 0..generations {
+            self.enqueue(command_encoder);
+        }
+    }
+
+    fn dispatch(
+        &mut self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+    ) {
         let mut pass_encoder =
             command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
-        pass_encoder.set_pipeline(&self.compute_pipeline);
+        pass_encoder.set_pipeline(pipeline);
 
         pass_encoder.set_bind_group(
             0,
@@ -243,185 +1319,427 @@ impl Computer {
 
         self.currently_computed_is_0 = !self.currently_computed_is_0;
 
-        let workgroup_width = 8;
-        assert_eq!(self.cells_width % workgroup_width, 0);
-        assert_eq!(self.cells_height % workgroup_width, 0);
-        let workgroup_count_x = self.cells_width.div_ceil(workgroup_width);
-        let workgroup_count_y = self.cells_height.div_ceil(workgroup_width);
-        let workgroup_count_z = 1;
+        let workgroup_count_x = self
+            .storage
+            .words_per_row(self.cells_width)
+            .div_ceil(WORKGROUP_WIDTH);
+        let workgroup_count_y = self.cells_height.div_ceil(WORKGROUP_WIDTH);
+        let workgroup_count_z = self.batch;
         pass_encoder.dispatch_workgroups(workgroup_count_x, workgroup_count_y, workgroup_count_z);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::vec;
-
     use super::*;
+    use crate::cpu::CpuSimulation;
+    use crate::simulation::Settings;
+    use crate::testing;
 
-    struct CpuBasedGameOfLife {
-        cells: Vec<u32>,
-        width: usize,
-        height: usize,
-    }
+    #[test]
+    fn test_computer() {
+        let (device, queue) = testing::device();
 
-    impl CpuBasedGameOfLife {
-        fn live_neighbours_at(&self, x: usize, y: usize) -> u32 {
-            let mut result = 0;
-            for dx in -1..=1 {
-                for dy in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let n_x = (x as i32 - dx).rem_euclid(self.width as i32) as usize;
-                    let n_y = (y as i32 - dy).rem_euclid(self.height as i32) as usize;
-                    if self.cells[n_x + n_y * self.width] > 0 {
-                        result += 1;
-                    }
-                }
+        for (cells_width, cells_height, topology, storage, kernel) in
+            [(64, 64), (128, 128), (96, 40), (100, 60), (13, 7)]
+                .into_iter()
+                .flat_map(|(width, height)| Topology::ALL.map(|topology| (width, height, topology)))
+                .filter(|&(width, height, topology)| topology.supports_size(width, height))
+                .flat_map(|(width, height, topology)| {
+                    [
+                        (CellStorage::Ages, Kernel::Direct),
+                        (CellStorage::Ages, Kernel::Tiled),
+                        (CellStorage::Bits, Kernel::default()),
+                    ]
+                    .map(|(storage, kernel)| (width, height, topology, storage, kernel))
+                })
+        {
+            let settings = Settings {
+                rule_idx: 0,
+                topology,
+                cells_width,
+                cells_height,
+                cell_storage: storage,
+                seed: 1,
+                initial_density: 50,
+            };
+            let mut computer = ComputerFactory::new(&device)
+                .with_kernel(kernel)
+                .create(
+                    &device,
+                    cells_width,
+                    cells_height,
+                    storage,
+                    settings.rule(),
+                    topology,
+                    settings.seed,
+                    settings.initial_density,
+                )
+                .unwrap();
+            let mut cpu_simulation = CpuSimulation::new(settings).unwrap();
+            let cells = testing::read_cells(&device, &queue, &computer);
+            assert_eq!(
+                storage.words_per_row(cells_width) as usize * cells_height as usize,
+                cells.words.len()
+            );
+            assert_eq!(cells.words, cpu_simulation.cells().words);
+
+            assert!(computer.currently_computed_is_0);
+            for iteration in 0..100 {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                computer.enqueue(&mut encoder);
+                assert!(computer.currently_computed_is_0 == (iteration % 2 == 1));
+                queue.submit(std::iter::once(encoder.finish()));
+
+                cpu_simulation.step(1);
+                assert_eq!(
+                    testing::read_cells(&device, &queue, &computer).words,
+                    cpu_simulation.cells().words,
+                    "Iteration {iteration} of {cells_width}x{cells_height} {topology:?} {storage:?} {kernel:?}"
+                );
             }
-            result
         }
-        fn next_generation(&mut self, rule: &Rule) {
-            let mut new_cells = vec![0; self.cells.len()];
-            for x in 0..self.width {
-                for y in 0..self.height {
-                    let current_generation = self.cells[x + y * self.width];
-                    let was_alive = current_generation > 0;
-                    let num_live_neighbours = self.live_neighbours_at(x, y);
-                    let new_is_alive = if was_alive { rule.survives } else { rule.born }
-                        & (1 << num_live_neighbours)
-                        > 0;
-                    new_cells[x + y * self.width] = if new_is_alive {
-                        current_generation + 1
-                    } else {
-                        0
-                    };
+    }
+
+    #[test]
+    fn test_enqueue_generations() {
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
+
+        for (cells_width, cells_height) in [(64, 64), (100, 60), (16, 16), (13, 7)] {
+            for topology in Topology::ALL {
+                if !topology.supports_size(cells_width, cells_height) {
+                    continue;
+                }
+                let settings = Settings {
+                    rule_idx: 0,
+                    topology,
+                    cells_width,
+                    cells_height,
+                    cell_storage: CellStorage::Ages,
+                    seed: 1,
+                    initial_density: 50,
+                };
+                let mut computer = factory
+                    .create(
+                        &device,
+                        cells_width,
+                        cells_height,
+                        CellStorage::Ages,
+                        settings.rule(),
+                        topology,
+                        settings.seed,
+                        settings.initial_density,
+                    )
+                    .unwrap();
+                let mut cpu_simulation = CpuSimulation::new(settings).unwrap();
+
+                // Both multiple and single generation dispatches:
+                for generations in [10, 1, 8] {
+                    let mut encoder =
+                        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                    computer.enqueue_generations(&mut encoder, generations);
+                    queue.submit(std::iter::once(encoder.finish()));
+                    cpu_simulation.step(generations);
+                    assert_eq!(
+                        testing::read_cells(&device, &queue, &computer).words,
+                        cpu_simulation.cells().words,
+                        "{generations} generations of {cells_width}x{cells_height} {topology:?}"
+                    );
                 }
             }
-            let _ = std::mem::replace(&mut self.cells, new_cells);
         }
     }
 
     #[test]
-    fn test_computer() {
-        async fn async_test_computer() {
-            let instance =
-                wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
-
-            let adapter = instance
-                .request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    compatible_surface: None,
-                    force_fallback_adapter: false,
-                })
-                .await
-                .unwrap();
-
-            let (device, queue) = adapter
-                .request_device(&wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    label: None,
-                    memory_hints: Default::default(),
-                    trace: Default::default(),
-                    experimental_features: Default::default(),
-                })
-                .await
-                .map_err(|e| format!("request_device failed: {e}"))
-                .unwrap();
-
-            for cells_width in [64, 128] {
-                let cells_height = cells_width;
+    fn test_write_cells() {
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
 
-                let creator = ComputerFactory::new(&device);
-                let seed = 1;
-                let initial_density = 50;
-                let rule = &crate::rules::RULES[0];
-                let mut computer = creator.create(
+        // A rectangle crossing a word boundary of bit-packed cells, in a row of three words:
+        let (cells_width, cells_height) = (70, 5);
+        let rect = Rect {
+            x: 29,
+            y: 1,
+            width: 6,
+            height: 3,
+        };
+        let values = (0..rect.width * rect.height)
+            .map(|i| i % 3)
+            .collect::<Vec<_>>();
+        for storage in [CellStorage::Ages, CellStorage::Bits] {
+            let computer = factory
+                .create(
                     &device,
                     cells_width,
                     cells_height,
-                    rule,
-                    seed,
-                    initial_density,
-                    &queue,
-                );
-                instance.poll_all(true);
+                    storage,
+                    &crate::rules::RULES[0],
+                    Topology::Torus,
+                    1,
+                    50,
+                )
+                .unwrap();
+            let read_cells = |computer: &Computer| testing::read_cells(&device, &queue, computer);
+            let before = read_cells(&computer);
+            computer
+                .write_cells(&device, &queue, rect, &values)
+                .unwrap();
+            computer.set_cell(&device, &queue, 69, 4, 1).unwrap();
+            // Scattered cells, with a bounding rectangle overlapping the one written before:
+            let scattered = [(40, 0), (66, 2), (33, 4)];
+            computer.set_cells(&device, &queue, &scattered, 2).unwrap();
+            let after = read_cells(&computer);
+            assert_eq!(after.get(69, 4), 1);
+
+            for y in 0..cells_height {
+                for x in 0..cells_width {
+                    let inside = (rect.x..rect.x + rect.width).contains(&x)
+                        && (rect.y..rect.y + rect.height).contains(&y);
+                    let expected = if inside {
+                        let value = values[((y - rect.y) * rect.width + x - rect.x) as usize];
+                        match storage {
+                            CellStorage::Ages => value,
+                            CellStorage::Bits => u32::from(value > 0),
+                        }
+                    } else if (x, y) == (69, 4) {
+                        1
+                    } else if scattered.contains(&(x, y)) {
+                        match storage {
+                            CellStorage::Ages => 2,
+                            CellStorage::Bits => 1,
+                        }
+                    } else {
+                        before.get(x, y)
+                    };
+                    assert_eq!(after.get(x, y), expected, "({x}, {y}) {storage:?}");
+                }
+            }
 
-                let copy_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("copy_buffer"),
-                    size: computer.cells_buffer_1.size(),
-                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
+            assert!(computer.set_cell(&device, &queue, 70, 0, 1).is_err());
+            assert!(computer.write_cells(&device, &queue, rect, &[1]).is_err());
+            assert!(
+                computer
+                    .set_cells(&device, &queue, &[(0, 0), (0, 5)], 1)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_statistics() {
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
 
+        // A width not divisible by 32, to have bit-packed words extending outside the grid:
+        let (cells_width, cells_height) = (70, 40);
+        for storage in [CellStorage::Ages, CellStorage::Bits] {
+            let create_computer = || {
+                factory
+                    .create(
+                        &device,
+                        cells_width,
+                        cells_height,
+                        storage,
+                        &crate::rules::RULES[0],
+                        Topology::Torus,
+                        1,
+                        50,
+                    )
+                    .unwrap()
+            };
+            let advance = |computer: &mut Computer, generations| {
                 let mut encoder =
                     device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                encoder.copy_buffer_to_buffer(
-                    &computer.cells_buffer_0,
-                    0,
-                    &copy_buffer,
-                    0,
-                    copy_buffer.size(),
-                );
+                computer.enqueue_generations(&mut encoder, generations);
                 queue.submit(std::iter::once(encoder.finish()));
-                let gpu_read_buffer_slice = copy_buffer.slice(..);
-                gpu_read_buffer_slice.map_async(wgpu::MapMode::Read, Result::unwrap);
-                instance.poll_all(true);
-                let gpu_read_buffer_range = gpu_read_buffer_slice.get_mapped_range();
-                let cells_data: &[u32] = bytemuck::cast_slice(&gpu_read_buffer_range);
-                assert_eq!(
-                    cells_width as usize * cells_height as usize,
-                    cells_data.len()
-                );
-                let mut cpu_game_of_life = CpuBasedGameOfLife {
-                    cells: cells_data.to_vec(),
-                    width: cells_width as usize,
-                    height: cells_height as usize,
-                };
-                drop(gpu_read_buffer_range);
-                copy_buffer.unmap();
+            };
+            let read_cells = |computer: &Computer| testing::read_cells(&device, &queue, computer);
 
-                assert!(computer.currently_computed_is_0);
-                for iteration in 0..100 {
+            // Several generations advanced at once, compared to the generation before:
+            let mut computer = create_computer();
+            advance(&mut computer, 8);
+            let mut previous_computer = create_computer();
+            advance(&mut previous_computer, 7);
+            let (previous, current) = (read_cells(&previous_computer), read_cells(&computer));
+
+            let mut expected = Statistics::default();
+            let mut expected_hash = [0_u32; 2];
+            for y in 0..cells_height {
+                for x in 0..cells_width {
+                    let (was_alive, alive) = (previous.get(x, y) > 0, current.get(x, y) > 0);
+                    expected.population += u32::from(alive);
+                    expected.births += u32::from(alive && !was_alive);
+                    expected.deaths += u32::from(was_alive && !alive);
+                    if alive {
+                        let [low, high] = cell_hash(y * cells_width + x);
+                        expected_hash = [
+                            expected_hash[0].wrapping_add(low),
+                            expected_hash[1].wrapping_add(high),
+                        ];
+                    }
+                }
+            }
+            expected.hash = u64::from(expected_hash[1]) << 32 | u64::from(expected_hash[0]);
+            assert!(expected.population > 0 && expected.births > 0 && expected.deaths > 0);
+
+            assert_eq!(
+                pollster::block_on(computer.statistics_async(&device, &queue)),
+                Ok(expected),
+                "{storage:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch() {
+        use std::sync::{Arc, Mutex};
+
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
+
+        let seeds = [1, 2, 3];
+        let (cells_width, cells_height) = (70, 40);
+        for storage in [CellStorage::Ages, CellStorage::Bits] {
+            for topology in [Topology::Torus, Topology::Plane] {
+                let advance = |computer: &mut Computer| {
                     let mut encoder =
                         device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                    computer.enqueue(&mut encoder);
-                    assert!(computer.currently_computed_is_0 == (iteration % 2 == 1));
-
-                    encoder.copy_buffer_to_buffer(
-                        if computer.currently_computed_is_0 {
-                            &computer.cells_buffer_0
-                        } else {
-                            &computer.cells_buffer_1
-                        },
-                        0,
-                        &copy_buffer,
-                        0,
-                        copy_buffer.size(),
-                    );
-
+                    computer.enqueue_generations(&mut encoder, 9);
                     queue.submit(std::iter::once(encoder.finish()));
-                    instance.poll_all(true);
-
-                    let gpu_read_buffer_slice = copy_buffer.slice(..);
-                    gpu_read_buffer_slice.map_async(wgpu::MapMode::Read, Result::unwrap);
-                    instance.poll_all(true);
-                    let gpu_read_buffer_range = gpu_read_buffer_slice.get_mapped_range();
-                    let cells_data: &[u32] = bytemuck::cast_slice(&gpu_read_buffer_range);
-                    cpu_game_of_life.next_generation(rule);
+                };
+                let read = |computer: &Computer| {
+                    let cells =
+                        pollster::block_on(computer.read_batch_cells(&device, &queue)).unwrap();
+
+                    let statistics = Arc::new(Mutex::new(None));
+                    let statistics_in_callback = Arc::clone(&statistics);
+                    computer.batch_statistics(&device, &queue, move |statistics| {
+                        *statistics_in_callback.lock().unwrap() = Some(statistics);
+                    });
+                    device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+                    let statistics = statistics.lock().unwrap().take().unwrap();
+                    (cells, statistics)
+                };
+
+                let mut batch_computer = factory
+                    .create_batch(
+                        &device,
+                        cells_width,
+                        cells_height,
+                        storage,
+                        &crate::rules::RULES[0],
+                        topology,
+                        &seeds,
+                        50,
+                    )
+                    .unwrap();
+                assert_eq!(batch_computer.batch(), 3);
+                advance(&mut batch_computer);
+                let (batch_cells, batch_statistics) = read(&batch_computer);
+                assert_eq!(batch_cells.len(), seeds.len());
+
+                for (index, &seed) in seeds.iter().enumerate() {
+                    let mut computer = factory
+                        .create(
+                            &device,
+                            cells_width,
+                            cells_height,
+                            storage,
+                            &crate::rules::RULES[0],
+                            topology,
+                            seed,
+                            50,
+                        )
+                        .unwrap();
+                    advance(&mut computer);
+                    let (cells, statistics) = read(&computer);
                     assert_eq!(
-                        &cpu_game_of_life.cells[..],
-                        cells_data,
-                        "Iteration: {}",
-                        iteration
+                        batch_cells[index].words, cells[0].words,
+                        "Seed {seed} of {storage:?} {topology:?}"
+                    );
+                    assert_eq!(
+                        batch_statistics[index], statistics[0],
+                        "Seed {seed} of {storage:?} {topology:?}"
                     );
-                    drop(gpu_read_buffer_range);
-                    copy_buffer.unmap();
                 }
             }
         }
-        pollster::block_on(async_test_computer());
+
+        assert!(
+            factory
+                .create_batch(
+                    &device,
+                    cells_width,
+                    cells_height,
+                    CellStorage::Ages,
+                    &crate::rules::RULES[0],
+                    Topology::Torus,
+                    &[],
+                    50,
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_independent_computers() {
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
+        let settings = Settings {
+            cells_width: 40,
+            cells_height: 20,
+            ..Settings::default()
+        };
+        let create_computer = |settings: Settings| {
+            factory
+                .create(
+                    &device,
+                    settings.cells_width,
+                    settings.cells_height,
+                    settings.cell_storage,
+                    settings.rule(),
+                    settings.topology,
+                    settings.seed,
+                    settings.initial_density,
+                )
+                .unwrap()
+        };
+        let mut computer = create_computer(settings);
+        // Creating another computer of a different size and rule does not affect the first:
+        let _other = create_computer(Settings {
+            cells_width: 16,
+            cells_height: 64,
+            rule_idx: 1,
+            ..settings
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        computer.enqueue_generations(&mut encoder, 5);
+        queue.submit(std::iter::once(encoder.finish()));
+        let mut cpu_simulation = CpuSimulation::new(settings).unwrap();
+        cpu_simulation.step(5);
+        assert_eq!(
+            testing::read_cells(&device, &queue, &computer).words,
+            cpu_simulation.cells().words
+        );
+    }
+
+    #[test]
+    fn test_validate_size() {
+        let limits = wgpu::Limits {
+            max_buffer_size: 1 << 20,
+            max_storage_buffer_binding_size: 1 << 20,
+            ..wgpu::Limits::default()
+        };
+        assert!(validate_size(&limits, 1, 1, CellStorage::Ages).is_ok());
+        assert!(validate_size(&limits, 1000, 262, CellStorage::Ages).is_ok());
+        assert!(validate_size(&limits, 1000, 263, CellStorage::Ages).is_err());
+        assert!(validate_size(&limits, 1000, 263, CellStorage::Bits).is_ok());
+        assert!(validate_size(&limits, 4096, 2048, CellStorage::Bits).is_ok());
+        assert!(validate_size(&limits, 4096, 2049, CellStorage::Bits).is_err());
+        assert!(validate_size(&limits, 0, 64, CellStorage::Bits).is_err());
+        assert!(validate_size(&limits, u32::MAX, 1, CellStorage::Ages).is_err());
     }
 }
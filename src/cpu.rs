@@ -0,0 +1,436 @@
+//! Simulation on the CPU, for machines without a suitable GPU adapter and as a reference
+//! that the GPU computers can be compared with.
+//!
+//! Cells are kept bit-packed as in [`CellStorage::Bits`], and the 32 cells of a word that is
+//! not at an edge are computed at once by adding up neighbours with bitwise operations, as in
+//! `game-of-life.compute-bits.wgsl`. The rows of large grids are split between threads.
+
+use std::num::NonZeroUsize;
+
+use crate::computer::{self, CellStorage, Cells, Statistics};
+use crate::selection::Rect;
+use crate::simulation::{Backend, BoxFuture, Settings};
+use crate::topology::Topology;
+
+/// Grids with fewer cells for each thread are computed by fewer threads, as starting a
+/// thread takes about as long as computing this many cells.
+const MIN_CELLS_PER_THREAD: usize = 1 << 18;
+
+pub struct CpuSimulation {
+    settings: Settings,
+    generation: u64,
+    /// The live cells, bit-packed.
+    cells: Vec<u32>,
+    /// The live cells of the previous generation, to count births and deaths.
+    previous: Vec<u32>,
+    /// The ages of the cells, kept when the settings store [`CellStorage::Ages`].
+    ages: Option<Vec<u32>>,
+}
+
+/// The bit-packed cells of a generation, and the rule to compute the next one with.
+struct Grid<'a> {
+    cells: &'a [u32],
+    cells_width: u32,
+    cells_height: u32,
+    words_per_row: usize,
+    topology: Topology,
+    born: u32,
+    survives: u32,
+}
+
+impl Grid<'_> {
+    fn is_alive(&self, x: i32, y: i32) -> u32 {
+        self.topology
+            .cell_at(x, y, self.cells_width, self.cells_height)
+            .map_or(0, |(x, y)| {
+                (self.cells[y as usize * self.words_per_row + x as usize / 32] >> (x % 32)) & 1
+            })
+    }
+
+    const fn next_state(&self, neighbours: u32, alive: u32) -> u32 {
+        let rule_mask = if alive == 1 { self.survives } else { self.born };
+        (rule_mask >> neighbours) & 1
+    }
+
+    /// Compute the word one cell at a time, used at the edges where neighbours
+    /// depend on the topology.
+    fn next_word_by_cell(&self, word_x: usize, y: u32) -> u32 {
+        let mut result = 0;
+        for bit in 0..32 {
+            let x = word_x * 32 + bit;
+            if x >= self.cells_width as usize {
+                break;
+            }
+            let (x, y) = (x as i32, y as i32);
+            let neighbours = [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ]
+            .into_iter()
+            .map(|(dx, dy)| self.is_alive(x + dx, y + dy))
+            .sum();
+            result |= self.next_state(neighbours, self.is_alive(x, y)) << bit;
+        }
+        result
+    }
+
+    /// Compute all 32 cells of a word at once, for words not at an edge so that all
+    /// neighbouring words are inside the grid.
+    fn next_word_bit_parallel(&self, index: usize) -> u32 {
+        /// Add a bit for each cell to a 4 bit counter per cell, where bit i of `counter[n]`
+        /// is bit n of the count for cell i.
+        fn add(counter: &mut [u32; 4], word: u32) {
+            let mut carry = word;
+            for bits in counter {
+                let sum = *bits ^ carry;
+                carry &= *bits;
+                *bits = sum;
+            }
+        }
+
+        let mut counter = [0; 4];
+        for (row_index, include_center) in [
+            (index - self.words_per_row, true),
+            (index, false),
+            (index + self.words_per_row, true),
+        ] {
+            let left = self.cells[row_index - 1];
+            let center = self.cells[row_index];
+            let right = self.cells[row_index + 1];
+            // The neighbours to the left of cell i are at bit i - 1, spilling over from the previous word:
+            add(&mut counter, (center << 1) | (left >> 31));
+            add(&mut counter, (center >> 1) | (right << 31));
+            if include_center {
+                add(&mut counter, center);
+            }
+        }
+
+        let alive = self.cells[index];
+        let mut result = 0;
+        for neighbours in 0..=8 {
+            let has_count = counter
+                .iter()
+                .enumerate()
+                .fold(!0, |has_count, (n, &bits)| {
+                    has_count
+                        & if (neighbours >> n) & 1 == 1 {
+                            bits
+                        } else {
+                            !bits
+                        }
+                });
+            let born = if (self.born >> neighbours) & 1 == 1 {
+                !alive
+            } else {
+                0
+            };
+            let survives = if (self.survives >> neighbours) & 1 == 1 {
+                alive
+            } else {
+                0
+            };
+            result |= has_count & (born | survives);
+        }
+        result
+    }
+
+    /// Compute the next generation into `next`, splitting the rows between `threads` threads.
+    fn next_generation(&self, next: &mut [u32], threads: usize) {
+        if threads <= 1 {
+            for (y, row) in next.chunks_exact_mut(self.words_per_row).enumerate() {
+                self.next_row(y as u32, row);
+            }
+            return;
+        }
+        let rows_per_thread = (self.cells_height as usize).div_ceil(threads);
+        std::thread::scope(|scope| {
+            for (chunk_index, chunk) in next
+                .chunks_mut(rows_per_thread * self.words_per_row)
+                .enumerate()
+            {
+                scope.spawn(move || {
+                    for (row_offset, row) in chunk.chunks_exact_mut(self.words_per_row).enumerate()
+                    {
+                        self.next_row((chunk_index * rows_per_thread + row_offset) as u32, row);
+                    }
+                });
+            }
+        });
+    }
+
+    fn next_row(&self, y: u32, next_row: &mut [u32]) {
+        let interior_row = y > 0 && y + 1 < self.cells_height;
+        for (word_x, next_word) in next_row.iter_mut().enumerate() {
+            // The last word of a row may be partially outside the grid, so it is not interior either:
+            *next_word = if interior_row && word_x > 0 && word_x + 1 < self.words_per_row {
+                self.next_word_bit_parallel(y as usize * self.words_per_row + word_x)
+            } else {
+                self.next_word_by_cell(word_x, y)
+            };
+        }
+    }
+}
+
+impl CpuSimulation {
+    /// Start from the same random cells as a GPU [`crate::simulation::Simulation`] with
+    /// the same settings.
+    pub fn new(settings: Settings) -> Result<Self, String> {
+        settings.validate_without_limits()?;
+        let random_words = |storage| {
+            computer::random_words(
+                storage,
+                settings.cells_width,
+                settings.cells_height,
+                settings.seed,
+                settings.initial_density,
+            )
+        };
+        let cells = random_words(CellStorage::Bits);
+        let ages =
+            (settings.cell_storage == CellStorage::Ages).then(|| random_words(CellStorage::Ages));
+        Ok(Self {
+            settings,
+            generation: 0,
+            // Births are counted from no cells before the first generation, as on the GPU:
+            previous: vec![0; cells.len()],
+            cells,
+            ages,
+        })
+    }
+
+    pub const fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// The number of generations computed since the simulation was started.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn words_per_row(&self) -> usize {
+        CellStorage::Bits.words_per_row(self.settings.cells_width) as usize
+    }
+
+    /// Advance generations.
+    pub fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.next_generation();
+        }
+    }
+
+    fn next_generation(&mut self) {
+        let words_per_row = self.words_per_row();
+        let rule = self.settings.rule();
+        std::mem::swap(&mut self.cells, &mut self.previous);
+        let grid = Grid {
+            cells: &self.previous,
+            cells_width: self.settings.cells_width,
+            cells_height: self.settings.cells_height,
+            words_per_row,
+            topology: self.settings.topology,
+            born: u32::from(rule.born),
+            survives: u32::from(rule.survives),
+        };
+
+        let cells_count = self.settings.cells_width as usize * self.settings.cells_height as usize;
+        let threads = std::thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(cells_count / MIN_CELLS_PER_THREAD);
+        grid.next_generation(&mut self.cells, threads);
+
+        if let Some(ages) = &mut self.ages {
+            let cells_width = self.settings.cells_width as usize;
+            for (y, ages_row) in ages.chunks_exact_mut(cells_width).enumerate() {
+                let row = &self.cells[y * words_per_row..(y + 1) * words_per_row];
+                for (x, age) in ages_row.iter_mut().enumerate() {
+                    *age = if (row[x / 32] >> (x % 32)) & 1 == 1 {
+                        age.wrapping_add(1)
+                    } else {
+                        0
+                    };
+                }
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// The current generation, laid out as the cell buffers of the GPU store it.
+    pub fn cells(&self) -> Cells {
+        Cells::new(
+            self.settings.cells_width,
+            self.settings.cell_storage,
+            self.ages.clone().unwrap_or_else(|| self.cells.clone()),
+        )
+    }
+
+    /// Count and hash the cells of the current generation as on the GPU, see
+    /// [`computer::Computer::statistics()`].
+    pub fn statistics(&self) -> Statistics {
+        let words_per_row = self.words_per_row();
+        let cells_width = self.settings.cells_width as usize;
+        let mut statistics = Statistics::default();
+        let mut hash = [0_u32; 2];
+        for (index, (&alive, &was_alive)) in self.cells.iter().zip(&self.previous).enumerate() {
+            statistics.population += alive.count_ones();
+            statistics.births += (alive & !was_alive).count_ones();
+            statistics.deaths += (was_alive & !alive).count_ones();
+            let first_index = index / words_per_row * cells_width + index % words_per_row * 32;
+            let mut bits = alive;
+            while bits != 0 {
+                let [low, high] = computer::cell_hash(first_index as u32 + bits.trailing_zeros());
+                hash = [hash[0].wrapping_add(low), hash[1].wrapping_add(high)];
+                bits &= bits - 1;
+            }
+        }
+        statistics.hash = u64::from(hash[1]) << 32 | u64::from(hash[0]);
+        statistics
+    }
+
+    /// Write a rectangle of cells, where `values` holds the rows of the rectangle with
+    /// 0 for dead cells and the age of live cells.
+    pub fn write_cells(&mut self, rect: Rect, values: &[u32]) -> Result<(), String> {
+        computer::validate_rect(
+            rect,
+            values,
+            self.settings.cells_width,
+            self.settings.cells_height,
+        )?;
+        let words_per_row = self.words_per_row();
+        let cells_width = self.settings.cells_width as usize;
+        for (index, &value) in values.iter().enumerate() {
+            let x = rect.x as usize + index % rect.width as usize;
+            let y = rect.y as usize + index / rect.width as usize;
+            let word = &mut self.cells[y * words_per_row + x / 32];
+            *word = (*word & !(1 << (x % 32))) | (u32::from(value > 0) << (x % 32));
+            if let Some(ages) = &mut self.ages {
+                ages[y * cells_width + x] = value;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Backend for CpuSimulation {
+    fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn advance(&mut self, generations: u64) {
+        self.step(generations);
+    }
+
+    fn write_cells(&mut self, rect: Rect, values: &[u32]) -> Result<(), String> {
+        Self::write_cells(self, rect, values)
+    }
+
+    fn fetch_cells(&self) -> BoxFuture<'_, Result<Cells, String>> {
+        Box::pin(std::future::ready(Ok(self.cells())))
+    }
+
+    fn fetch_statistics(&self) -> BoxFuture<'_, Result<Statistics, String>> {
+        Box::pin(std::future::ready(Ok(self.statistics())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::testing;
+
+    #[test]
+    fn test_cpu_simulation() {
+        let (device, queue) = testing::device();
+
+        // Widths not divisible by 32, and a grid large enough to be split between threads:
+        for (cells_width, cells_height) in [(70, 40), (64, 64), (13, 7), (1030, 260)] {
+            for topology in Topology::ALL {
+                for cell_storage in [CellStorage::Ages, CellStorage::Bits] {
+                    let settings = Settings {
+                        rule_idx: 0,
+                        topology,
+                        cells_width,
+                        cells_height,
+                        cell_storage,
+                        seed: 3,
+                        initial_density: 40,
+                    };
+                    if settings.validate(&device.limits()).is_err() {
+                        continue;
+                    }
+                    let mut backends: [Box<dyn Backend>; 2] = [
+                        Box::new(CpuSimulation::new(settings).unwrap()),
+                        Box::new(Simulation::new(device.clone(), queue.clone(), settings).unwrap()),
+                    ];
+                    let rect = Rect {
+                        x: 1,
+                        y: 2,
+                        width: 3,
+                        height: 1,
+                    };
+                    for backend in &mut backends {
+                        backend.write_cells(rect, &[1, 0, 5]).unwrap();
+                        assert!(backend.write_cells(rect, &[1]).is_err());
+                    }
+
+                    for generations in [0, 1, 4, 9] {
+                        let [cpu, gpu] = backends.each_mut().map(|backend| {
+                            backend.advance(generations);
+                            let cells = pollster::block_on(backend.fetch_cells()).unwrap();
+                            let statistics =
+                                pollster::block_on(backend.fetch_statistics()).unwrap();
+                            (backend.generation(), cells.into_words(), statistics)
+                        });
+                        assert!(
+                            cpu == gpu,
+                            "Generation {} of {settings:?}",
+                            backends[0].generation()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let settings = Settings {
+            cells_width: 100,
+            cells_height: 37,
+            topology: Topology::KleinBottle {
+                top_bottom_twisted: true,
+            },
+            ..Settings::default()
+        };
+        let simulation = CpuSimulation::new(settings).unwrap();
+        let grid = Grid {
+            cells: &simulation.cells,
+            cells_width: settings.cells_width,
+            cells_height: settings.cells_height,
+            words_per_row: simulation.words_per_row(),
+            topology: settings.topology,
+            born: u32::from(settings.rule().born),
+            survives: u32::from(settings.rule().survives),
+        };
+        let next = |threads| {
+            let mut next = vec![0; simulation.cells.len()];
+            grid.next_generation(&mut next, threads);
+            next
+        };
+        let expected = next(1);
+        assert_ne!(expected, simulation.cells);
+        for threads in [2, 3, 8, 37, 50] {
+            assert_eq!(next(threads), expected, "{threads} threads");
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/// An ongoing mouse or touch stroke which paints cells as the pointer moves.
+#[derive(Clone, Copy)]
+pub(crate) struct Drawing {
+    /// If cells are drawn (made alive) or erased (made dead).
+    pub(crate) alive: bool,
+    /// The last cell painted, used to paint a connected line to the next one.
+    pub(crate) last_cell: Option<(u32, u32)>,
+}
+
+/// The cells on the line between `from` and `to` (both inclusive), using Bresenham's algorithm.
+pub(crate) fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (mut x, mut y) = (i64::from(from.0), i64::from(from.1));
+    let (to_x, to_y) = (i64::from(to.0), i64::from(to.1));
+    let dx = (to_x - x).abs();
+    let dy = -(to_y - y).abs();
+    let step_x = if x < to_x { 1 } else { -1 };
+    let step_y = if y < to_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut result = Vec::with_capacity((dx - dy + 1) as usize);
+    loop {
+        result.push((x as u32, y as u32));
+        if x == to_x && y == to_y {
+            return result;
+        }
+        let doubled_error = 2 * error;
+        if doubled_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(line((3, 4), (3, 4)), vec![(3, 4)]);
+        assert_eq!(line((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+        assert_eq!(line((0, 0), (1, 3)), vec![(0, 0), (0, 1), (1, 2), (1, 3)]);
+    }
+}
@@ -1,32 +1,56 @@
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, KeyEvent, WindowEvent},
-    event_loop::ActiveEventLoop,
+    event::{
+        ElementState, KeyEvent, MouseButton, MouseScrollDelta, Touch, TouchPhase, WindowEvent,
+    },
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    keyboard::NamedKey,
     window::WindowId,
 };
 
 use crate::State;
+use crate::transform::Transform;
+
+/// Events sent to the event loop from outside of it.
+#[derive(Clone, Copy, Debug)]
+pub enum UserEvent {
+    /// A batch of generations of [`State::advance_to()`] has been computed.
+    AdvanceBatchDone,
+}
 
-#[derive(Default)]
 pub struct App {
     state: Option<State>,
+    proxy: EventLoopProxy<UserEvent>,
 }
 
 impl App {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(event_loop: &EventLoop<UserEvent>) -> Self {
+        Self {
+            state: None,
+            proxy: event_loop.create_proxy(),
+        }
     }
 }
 
-impl ApplicationHandler for App {
+/// Let `state` wake up the event loop through `proxy`.
+pub fn set_wake_up(state: &mut State, proxy: &EventLoopProxy<UserEvent>) {
+    let proxy = proxy.clone();
+    state.set_wake_up(move || {
+        proxy.send_event(UserEvent::AdvanceBatchDone).ok();
+    });
+}
+
+impl ApplicationHandler<UserEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.state.is_none() {
             let window_attributes = winit::window::Window::default_attributes();
             let window = event_loop.create_window(window_attributes).unwrap();
-            self.state = Some(
-                pollster::block_on(State::new(window, None, None, None, None, false, None))
-                    .unwrap(),
-            );
+            let mut state = pollster::block_on(State::new(
+                window, None, None, None, None, None, None, false, None,
+            ))
+            .unwrap();
+            set_wake_up(&mut state, &self.proxy);
+            self.state = Some(state);
         }
         if let Some(state) = &mut self.state {
             state.last_time = web_time::Instant::now();
@@ -39,6 +63,14 @@ impl ApplicationHandler for App {
         }
     }
 
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        if let Some(state) = &mut self.state {
+            match event {
+                UserEvent::AdvanceBatchDone => state.continue_advance(),
+            }
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -55,6 +87,58 @@ impl ApplicationHandler for App {
 #[allow(unused_variables)]
 pub fn handle_window_event(event: &WindowEvent, state: &mut State, event_loop: &ActiveEventLoop) {
     match event {
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key,
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } if state.generation_prompt.is_some() => {
+            state.generation_prompt_input(logical_key);
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key: winit::keyboard::Key::Named(winit::keyboard::NamedKey::Escape),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } if state.is_advancing() => {
+            state.cancel_advance();
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key: winit::keyboard::Key::Named(key),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } if (state.modifiers.shift_key() || state.modifiers.alt_key())
+            && matches!(
+                key,
+                NamedKey::ArrowDown
+                    | NamedKey::ArrowUp
+                    | NamedKey::ArrowLeft
+                    | NamedKey::ArrowRight
+            ) =>
+        {
+            let (dx, dy) = match key {
+                NamedKey::ArrowDown => (0, -1),
+                NamedKey::ArrowUp => (0, 1),
+                NamedKey::ArrowLeft => (-1, 0),
+                _ => (1, 0),
+            };
+            if state.modifiers.alt_key() {
+                const PAN_FRACTION: f32 = 0.1;
+                state.pan(dx as f32 * PAN_FRACTION, dy as f32 * PAN_FRACTION);
+            } else {
+                state.apply_transform(&Transform::translate(dx, dy));
+            }
+        }
         WindowEvent::KeyboardInput {
             event:
                 KeyEvent {
@@ -86,7 +170,7 @@ pub fn handle_window_event(event: &WindowEvent, state: &mut State, event_loop: &
                 },
             ..
         } => {
-            state.set_initial_density(state.initial_density - 1);
+            state.set_initial_density(state.simulation().settings().initial_density - 1);
         }
         WindowEvent::KeyboardInput {
             event:
@@ -97,7 +181,67 @@ pub fn handle_window_event(event: &WindowEvent, state: &mut State, event_loop: &
                 },
             ..
         } => {
-            state.set_initial_density(state.initial_density + 1);
+            state.set_initial_density(state.simulation().settings().initial_density + 1);
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key: winit::keyboard::Key::Character(c),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } if state.modifiers.control_key() || state.modifiers.super_key() => {
+            if c == "a" || c == "A" {
+                state.select_all();
+            } else if c == "c" || c == "C" {
+                state.copy_selection();
+            } else if c == "x" || c == "X" {
+                state.cut_selection();
+            } else if c == "f" || c == "F" {
+                state.fill_selection();
+            } else if c == "r" || c == "R" {
+                state.randomize_selection();
+            } else if c == "z" || c == "Z" {
+                state.undo();
+            } else if c == "s" || c == "S" {
+                state.store_session();
+            } else if c == "o" || c == "O" {
+                #[cfg(target_family = "wasm")]
+                crate::web::request_stored_session();
+                #[cfg(not(target_family = "wasm"))]
+                state.restore_session();
+            }
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key:
+                        winit::keyboard::Key::Named(
+                            winit::keyboard::NamedKey::Delete
+                            | winit::keyboard::NamedKey::Backspace,
+                        ),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } => {
+            if state.modifiers.shift_key() {
+                state.clear_outside_selection();
+            } else {
+                state.clear_selection();
+            }
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    logical_key: winit::keyboard::Key::Named(winit::keyboard::NamedKey::Escape),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } if state.selection.is_some() => {
+            state.set_selection(None);
         }
         WindowEvent::KeyboardInput {
             event:
@@ -131,28 +275,73 @@ pub fn handle_window_event(event: &WindowEvent, state: &mut State, event_loop: &
                 crate::web::download_image();
             } else if c == "r" || c == "R" {
                 state.reset();
+            } else if c == "h" || c == "H" {
+                state.apply_transform(&Transform::mirror_horizontally(
+                    state.simulation().settings().cells_width,
+                ));
+            } else if c == "v" || c == "V" {
+                state.apply_transform(&Transform::mirror_vertically(
+                    state.simulation().settings().cells_height,
+                ));
+            } else if c == "]" || c == "[" || c == "\\" {
+                let quarter_turns = match c.as_str() {
+                    "]" => 1,
+                    "\\" => 2,
+                    _ => 3,
+                };
+                state.apply_transform(&Transform::rotate_clockwise(
+                    quarter_turns,
+                    state.simulation().settings().cells_width,
+                    state.simulation().settings().cells_height,
+                ));
+            } else if c == "k" || c == "K" {
+                state.crop_to_live_cells();
+            } else if c == "z" || c == "Z" {
+                state.zoom(1.5, false);
+            } else if c == "x" || c == "X" {
+                state.zoom(1. / 1.5, false);
+            } else if c == "0" {
+                state.reset_camera();
+            } else if c == "l" || c == "L" {
+                state.fit_camera_to_live_cells();
             } else if c == "q" || c == "Q" {
                 state.set_generations_per_second(state.generations_per_second - 1);
             } else if c == "<" {
-                state.set_initial_density(state.initial_density - 1);
+                state.set_initial_density(state.simulation().settings().initial_density - 1);
             } else if c == ">" {
-                state.set_initial_density(state.initial_density + 1);
+                state.set_initial_density(state.simulation().settings().initial_density + 1);
             } else if c == "w" || c == "W" {
                 state.set_generations_per_second(state.generations_per_second + 1);
-            } else if c == "-" && state.cells_width < 2048 {
-                let current_idx = State::ELIGIBLE_SIZES
-                    .iter()
-                    .position(|&s| s == state.cells_width)
-                    .unwrap();
-                let new_size = State::ELIGIBLE_SIZES[current_idx + 1];
-                state.reset_with_cells_width(new_size, new_size);
-            } else if c == "+" && state.cells_width > 64 {
-                let current_idx = State::ELIGIBLE_SIZES
-                    .iter()
-                    .position(|&s| s == state.cells_width)
-                    .unwrap();
-                let new_size = State::ELIGIBLE_SIZES[current_idx - 1];
-                state.reset_with_cells_width(new_size, new_size);
+            } else if c == "-" {
+                state.change_grid_size(true);
+            } else if c == "+" {
+                state.change_grid_size(false);
+            } else if c == "a" || c == "A" {
+                state.fit_grid_to_window();
+            } else if c == "t" || c == "T" {
+                state.change_topology();
+            } else if c == "b" || c == "B" {
+                state.toggle_cell_storage();
+            } else if c == "e" || c == "E" {
+                state.change_oscillation_action();
+            } else if c == "m" || c == "M" {
+                state.take_census();
+            } else if c == "p" || c == "P" {
+                state.show_population_graph = !state.show_population_graph;
+            } else if c == "y" || c == "Y" {
+                state.toggle_population_graph_log_scale();
+            } else if c == "u" || c == "U" {
+                state.set_turbo(!state.turbo);
+            } else if c == "g" || c == "G" {
+                state.start_generation_prompt();
+            } else if c == "n" || c == "N" || c == "." {
+                state.step();
+            } else if c == "," {
+                state.step_back();
+            } else if c == "{" || c == "}" {
+                state.change_step_exponent(c == "}");
+            } else if c == "|" {
+                state.toggle_step_base();
             }
         }
         #[cfg(not(target_family = "wasm"))]
@@ -190,6 +379,66 @@ pub fn handle_window_event(event: &WindowEvent, state: &mut State, event_loop: &
         } => {
             state.toggle_pause();
         }
+        WindowEvent::CursorMoved { position, .. } => {
+            state.set_cursor_position(Some(*position));
+        }
+        WindowEvent::CursorLeft { .. } => {
+            state.set_cursor_position(None);
+        }
+        WindowEvent::ModifiersChanged(modifiers) => {
+            state.modifiers = modifiers.state();
+        }
+        WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+            ..
+        } => {
+            if state.modifiers.shift_key() {
+                state.start_selecting();
+            } else if state.modifiers.alt_key() {
+                state.start_panning();
+            } else {
+                state.start_drawing(true);
+            }
+        }
+        WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Right,
+            ..
+        } => {
+            state.start_drawing(false);
+        }
+        WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Middle,
+            ..
+        } => {
+            state.start_panning();
+        }
+        WindowEvent::MouseInput {
+            state: ElementState::Released,
+            button: MouseButton::Left | MouseButton::Right | MouseButton::Middle,
+            ..
+        } => {
+            state.release_pointer();
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let factor = match delta {
+                MouseScrollDelta::LineDelta(_, lines) => 1.2_f32.powf(*lines),
+                MouseScrollDelta::PixelDelta(pixels) => 1.005_f32.powf(pixels.y as f32),
+            };
+            state.zoom(factor, true);
+        }
+        WindowEvent::Touch(Touch {
+            phase, location, ..
+        }) => {
+            state.set_cursor_position(Some(*location));
+            match phase {
+                TouchPhase::Started => state.start_drawing(true),
+                TouchPhase::Moved => {}
+                TouchPhase::Ended | TouchPhase::Cancelled => state.release_pointer(),
+            }
+        }
         WindowEvent::Resized(physical_size) => {
             state.resize(*physical_size);
         }
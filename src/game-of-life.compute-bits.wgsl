@@ -0,0 +1,149 @@
+// Computes the next generation with 32 cells packed into each u32, where bit i of word
+// `y * words_per_row + x / 32` holds the cell (x, y), with i = x % 32. Bits past the
+// width of the grid in the last word of each row are always 0.
+@binding(0) @group(0) var<storage, read> current: array<u32>;
+@binding(1) @group(0) var<storage, read_write> next: array<u32>;
+@binding(2) @group(0) var<uniform> size: vec2<u32>;
+@binding(3) @group(0) var<uniform> rule: vec2<i32>;
+@binding(4) @group(0) var<uniform> topology: vec4<u32>;
+
+// The index of the first word of the grid being computed, in a batch of grids stored
+// one after the other and dispatched along the z dimension.
+var<private> grid_offset: u32;
+
+// The rectangle of cells to write as (x, y, width, height), with its cells packed
+// into `rect_cells` in rows of `(width + 31) / 32` words, and `rect_mask` packed the
+// same way with the cells of the rectangle that are written.
+@binding(0) @group(1) var<storage, read_write> cells: array<u32>;
+@binding(1) @group(1) var<storage, read> rect_cells: array<u32>;
+@binding(2) @group(1) var<uniform> rect: vec4<u32>;
+@binding(3) @group(1) var<uniform> grid_size: vec2<u32>;
+@binding(4) @group(1) var<storage, read> rect_mask: array<u32>;
+
+fn words_per_row(width: u32) -> u32 {
+    return (width + 31u) / 32u;
+}
+
+fn is_alive(x: i32, y: i32) -> u32 {
+    let cell = topology_cell(x, y);
+    if cell.z == 0 {
+        return 0u;
+    }
+    let word = current[grid_offset + u32(cell.y) * words_per_row(size.x) + u32(cell.x) / 32u];
+    return (word >> (u32(cell.x) % 32u)) & 1u;
+}
+
+fn next_state(neighbours: u32, alive: u32) -> u32 {
+    let rule_mask = select(u32(rule.x), u32(rule.y), alive == 1u);
+    return (rule_mask >> neighbours) & 1u;
+}
+
+// Computes the word one cell at a time, used at the edges where neighbours
+// depend on the topology.
+fn next_word_by_cell(word_x: u32, y: u32) -> u32 {
+    var result = 0u;
+    for (var bit = 0u; bit < 32u; bit++) {
+        let x = word_x * 32u + bit;
+        if x >= size.x {
+            break;
+        }
+        let cx = i32(x);
+        let cy = i32(y);
+        let neighbours = is_alive(cx - 1, cy - 1) + is_alive(cx, cy - 1) + is_alive(cx + 1, cy - 1) + is_alive(cx - 1, cy) + is_alive(cx + 1, cy) + is_alive(cx - 1, cy + 1) + is_alive(cx, cy + 1) + is_alive(cx + 1, cy + 1);
+        result |= next_state(neighbours, is_alive(cx, cy)) << bit;
+    }
+    return result;
+}
+
+// A 4 bit counter per bit position, where bit i of `bits[n]` is bit n of the count for cell i.
+struct Counter {
+    bits: array<u32, 4>,
+}
+
+fn add(counter: ptr<function, Counter>, word: u32) {
+    var carry = word;
+    for (var n = 0; n < 4; n++) {
+        let sum = (*counter).bits[n] ^ carry;
+        carry &= (*counter).bits[n];
+        (*counter).bits[n] = sum;
+    }
+}
+
+fn add_row(counter: ptr<function, Counter>, index: u32, include_center: bool) {
+    let left = current[index - 1u];
+    let center = current[index];
+    let right = current[index + 1u];
+    // The neighbours to the left of cell i are at bit i - 1, spilling over from the previous word:
+    add(counter, (center << 1u) | (left >> 31u));
+    add(counter, (center >> 1u) | (right << 31u));
+    if include_center {
+        add(counter, center);
+    }
+}
+
+// Computes all 32 cells of a word at once by adding up neighbours with bitwise operations.
+// Only used for words not at an edge, so that all neighbouring words are inside the grid.
+fn next_word_bit_parallel(word_x: u32, y: u32) -> u32 {
+    let words = words_per_row(size.x);
+    let index = grid_offset + y * words + word_x;
+    var counter = Counter(array<u32, 4>(0u, 0u, 0u, 0u));
+    add_row(&counter, index - words, true);
+    add_row(&counter, index, false);
+    add_row(&counter, index + words, true);
+
+    let alive = current[index];
+    var result = 0u;
+    for (var neighbours = 0u; neighbours <= 8u; neighbours++) {
+        var has_count = ~0u;
+        for (var n = 0u; n < 4u; n++) {
+            has_count &= select(~counter.bits[n], counter.bits[n], ((neighbours >> n) & 1u) == 1u);
+        }
+        let born = select(0u, ~alive, ((u32(rule.x) >> neighbours) & 1u) == 1u);
+        let survives = select(0u, alive, ((u32(rule.y) >> neighbours) & 1u) == 1u);
+        result |= has_count & (born | survives);
+    }
+    return result;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) grid: vec3<u32>) {
+    let words = words_per_row(size.x);
+    if grid.x >= words || grid.y >= size.y {
+        return;
+    }
+    grid_offset = grid.z * words * size.y;
+    // The last word of a row may be partially outside the grid, so it is not interior either:
+    let interior = grid.x > 0u && grid.x + 1u < words && grid.y > 0u && grid.y + 1u < size.y;
+    let index = grid_offset + grid.y * words + grid.x;
+    if interior {
+        next[index] = next_word_bit_parallel(grid.x, grid.y);
+    } else {
+        next[index] = next_word_by_cell(grid.x, grid.y);
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn write_rect(@builtin(global_invocation_id) grid: vec3<u32>) {
+    let words = words_per_row(grid_size.x);
+    let y = rect.y + grid.y;
+    let word_x = rect.x / 32u + grid.x;
+    if y >= rect.y + rect.w || word_x * 32u >= rect.x + rect.z {
+        return;
+    }
+    let index = y * words + word_x;
+    var word = cells[index];
+    for (var bit = 0u; bit < 32u; bit++) {
+        let x = word_x * 32u + bit;
+        if x < rect.x || x >= rect.x + rect.z {
+            continue;
+        }
+        let rect_x = x - rect.x;
+        let rect_index = grid.y * words_per_row(rect.z) + rect_x / 32u;
+        if ((rect_mask[rect_index] >> (rect_x % 32u)) & 1u) == 0u {
+            continue;
+        }
+        let value = (rect_cells[rect_index] >> (rect_x % 32u)) & 1u;
+        word = (word & ~(1u << bit)) | (value << bit);
+    }
+    cells[index] = word;
+}
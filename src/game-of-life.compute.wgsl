@@ -2,20 +2,18 @@
 @binding(1) @group(0) var<storage, read_write> next: array<u32>;
 @binding(2) @group(0) var<uniform> size: vec2<u32>;
 @binding(3) @group(0) var<uniform> rule: vec2<i32>;
+@binding(4) @group(0) var<uniform> topology: vec4<u32>;
 
-fn modulo_euclidean(a: i32, b: i32) -> i32 {
-    let m = a % b;
-    return m + select(0, b, m < 0);
-}
-
-fn get_index(x: i32, y: i32) -> u32 {
-    let w = i32(size.x);
-    let h = i32(size.y);
-    return u32(modulo_euclidean(y, h) * w + modulo_euclidean(x, w));
-}
+// The index of the first cell of the grid being computed, in a batch of grids stored
+// one after the other and dispatched along the z dimension.
+var<private> grid_offset: u32;
 
 fn get_cell(x: i32, y: i32) -> u32 {
-    return current[get_index(x, y)];
+    let cell = topology_cell(x, y);
+    if cell.z == 0 {
+        return 0u;
+    }
+    return current[grid_offset + u32(cell.y) * size.x + u32(cell.x)];
 }
 
 fn is_alive(x: i32, y: i32) -> u32 {
@@ -26,14 +24,113 @@ fn count_neighbors(x: i32, y: i32) -> u32 {
     return is_alive(x - 1, y - 1) + is_alive(x, y - 1) + is_alive(x + 1, y - 1) + is_alive(x - 1, y) + is_alive(x + 1, y) + is_alive(x - 1, y + 1) + is_alive(x, y + 1) + is_alive(x + 1, y + 1);
 }
 
+fn next_generation(current_generation: u32, n: u32) -> u32 {
+    let cell_lives = current_generation >= 1u;
+    let will_be_born = u32(((1u << n) & u32(rule.x)) > 0u);
+    let will_survive = u32(((1u << n) & u32(rule.y)) > 0u) * (1u + current_generation);
+    return select(will_be_born, will_survive, cell_lives);
+}
+
 @compute @workgroup_size(8, 8)
 fn main(@builtin(global_invocation_id) grid: vec3<u32>) {
+    // The last workgroups extend outside grids with sizes not divisible by the workgroup size:
+    if grid.x >= size.x || grid.y >= size.y {
+        return;
+    }
+    grid_offset = grid.z * size.x * size.y;
     let x = i32(grid.x);
     let y = i32(grid.y);
     let n = count_neighbors(x, y);
-    let current_generation = get_cell(x, y);
-    let cell_lives = current_generation >= 1u;
-    let will_be_born = u32(((1u << n) & u32(rule.x)) > 0u);
-    let will_survive = u32(((1u << n) & u32(rule.y)) > 0u) * (1u + current_generation);
-    next[get_index(x, y)] = select(will_be_born, will_survive, cell_lives);
-} 
+    let index = grid_offset + grid.y * size.x + grid.x;
+    next[index] = next_generation(current[index], n);
+}
+
+// The cells of a workgroup surrounded by their neighbours, so that each cell is read
+// once per workgroup from the storage buffer instead of once by each of its neighbours.
+const TILE_WIDTH: u32 = 10u;
+var<workgroup> tile: array<u32, 100>;
+
+fn tile_alive(x: u32, y: u32) -> u32 {
+    return u32(tile[y * TILE_WIDTH + x] > 0u);
+}
+
+@compute @workgroup_size(8, 8)
+fn main_tiled(
+    @builtin(global_invocation_id) grid: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+) {
+    grid_offset = grid.z * size.x * size.y;
+    for (var i = local_index; i < TILE_WIDTH * TILE_WIDTH; i += 64u) {
+        let x = i32(workgroup.x * 8u + i % TILE_WIDTH) - 1;
+        let y = i32(workgroup.y * 8u + i / TILE_WIDTH) - 1;
+        // Cells more than one step outside the grid are only neighbours of cells
+        // outside the grid, in the last workgroups:
+        tile[i] = select(0u, get_cell(x, y), x <= i32(size.x) && y <= i32(size.y));
+    }
+    workgroupBarrier();
+
+    if grid.x >= size.x || grid.y >= size.y {
+        return;
+    }
+    let x = local.x + 1u;
+    let y = local.y + 1u;
+    let n = tile_alive(x - 1u, y - 1u) + tile_alive(x, y - 1u) + tile_alive(x + 1u, y - 1u) + tile_alive(x - 1u, y) + tile_alive(x + 1u, y) + tile_alive(x - 1u, y + 1u) + tile_alive(x, y + 1u) + tile_alive(x + 1u, y + 1u);
+    next[grid_offset + grid.y * size.x + grid.x] = next_generation(tile[y * TILE_WIDTH + x], n);
+}
+
+// Advances `GENERATIONS` generations per dispatch. Each workgroup loads its cells surrounded
+// by `GENERATIONS` rings of neighbours, and computes a ring less of them each generation
+// until only its own cells remain. Only used for topologies where cells further than
+// one step outside the grid are mapped by `topology_cell()` to cells evolving the same way.
+const GENERATIONS: u32 = 4u;
+const MULTI_TILE_WIDTH: u32 = 16u;
+var<workgroup> multi_tiles: array<array<u32, 256>, 2>;
+var<workgroup> multi_exists: array<bool, 256>;
+
+fn multi_tile_alive(tile: u32, x: u32, y: u32) -> u32 {
+    return u32(multi_tiles[tile][y * MULTI_TILE_WIDTH + x] > 0u);
+}
+
+@compute @workgroup_size(8, 8)
+fn main_multi(
+    @builtin(global_invocation_id) grid: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+) {
+    grid_offset = grid.z * size.x * size.y;
+    for (var i = local_index; i < MULTI_TILE_WIDTH * MULTI_TILE_WIDTH; i += 64u) {
+        let cell = topology_cell(
+            i32(workgroup.x * 8u + i % MULTI_TILE_WIDTH) - i32(GENERATIONS),
+            i32(workgroup.y * 8u + i / MULTI_TILE_WIDTH) - i32(GENERATIONS),
+        );
+        multi_exists[i] = cell.z == 1;
+        multi_tiles[0][i] = select(0u, current[grid_offset + u32(cell.y) * size.x + u32(cell.x)], cell.z == 1);
+        multi_tiles[1][i] = 0u;
+    }
+    workgroupBarrier();
+
+    for (var generation = 1u; generation <= GENERATIONS; generation++) {
+        let source = (generation - 1u) % 2u;
+        let destination = generation % 2u;
+        for (var i = local_index; i < MULTI_TILE_WIDTH * MULTI_TILE_WIDTH; i += 64u) {
+            let x = i % MULTI_TILE_WIDTH;
+            let y = i / MULTI_TILE_WIDTH;
+            let inside = x >= generation && x < MULTI_TILE_WIDTH - generation && y >= generation && y < MULTI_TILE_WIDTH - generation;
+            // Cells outside a plane stay dead:
+            if inside && multi_exists[i] {
+                let n = multi_tile_alive(source, x - 1u, y - 1u) + multi_tile_alive(source, x, y - 1u) + multi_tile_alive(source, x + 1u, y - 1u) + multi_tile_alive(source, x - 1u, y) + multi_tile_alive(source, x + 1u, y) + multi_tile_alive(source, x - 1u, y + 1u) + multi_tile_alive(source, x, y + 1u) + multi_tile_alive(source, x + 1u, y + 1u);
+                multi_tiles[destination][i] = next_generation(multi_tiles[source][i], n);
+            }
+        }
+        workgroupBarrier();
+    }
+
+    if grid.x >= size.x || grid.y >= size.y {
+        return;
+    }
+    let tile_index = (local.y + GENERATIONS) * MULTI_TILE_WIDTH + local.x + GENERATIONS;
+    next[grid_offset + grid.y * size.x + grid.x] = multi_tiles[GENERATIONS % 2u][tile_index];
+}
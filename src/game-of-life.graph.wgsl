@@ -0,0 +1,35 @@
+// A line graph of values between 0 and 1, drawn within a viewport over the grid.
+
+struct Graph {
+    // The number of values, with the latest at the right edge:
+    count: u32,
+    // The number of values spanning the width of the graph:
+    capacity: u32,
+}
+
+@group(0) @binding(0) var<storage, read> values: array<f32>;
+@group(0) @binding(1) var<uniform> graph: Graph;
+
+@vertex
+fn background_vertex_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn background_fragment_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 0.0, 0.7);
+}
+
+@vertex
+fn line_vertex_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let x = f32(graph.capacity - graph.count + vertex_index) / f32(max(graph.capacity - 1u, 1u));
+    // Leaving a margin so that the line is visible at the top and bottom:
+    let y = values[vertex_index] * 0.9 + 0.05;
+    return vec4<f32>(vec2<f32>(x, y) * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn line_fragment_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.2, 1.0, 0.4, 1.0);
+}
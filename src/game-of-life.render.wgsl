@@ -4,19 +4,33 @@ struct Out {
 }
 
 @binding(0) @group(0) var<uniform> size: vec2<u32>;
+// The selected rectangle as (x, y, width, height):
+@binding(1) @group(0) var<uniform> selection: vec4<u32>;
+
+struct Camera {
+    // The grid position, ranging from (0, 0) to (1, 1), shown at the center:
+    center: vec2<f32>,
+    // How much the grid is magnified horizontally and vertically:
+    scale: vec2<f32>,
+}
+@binding(2) @group(0) var<uniform> camera: Camera;
+
+// Transform a position in cell coordinates to clip space through the camera.
+fn to_clip_space(x: u32, y: u32) -> vec4<f32> {
+    let grid_position = vec2<f32>(f32(x) / f32(size.x), f32(y) / f32(size.y));
+    return vec4<f32>((grid_position - camera.center) * 2. * camera.scale, 0., 1.);
+}
 
 @vertex
 fn vertex_main(@builtin(instance_index) i: u32, @location(0) cell: u32, @location(1) pos: vec2<u32>) -> Out {
-    let w = size.x;
-    let h = size.y;
-    let x = (f32(i % w + pos.x) / f32(w) - 0.5) * 2. * f32(w) / f32(w);
-    let y = (f32((i - (i % w)) / w + pos.y) / f32(h) - 0.5) * 2. * f32(h) / f32(h);
+    let x = i % size.x + pos.x;
+    let y = i / size.x + pos.y;
 
     let max_age_for_color: u32 = 40u;
     let intensity = 1.0 - f32(min(cell, max_age_for_color)) / f32(max_age_for_color);
 
     let rgb = select(vec3(0., 0., 0.), spectral_bruton(intensity), cell > 0u);
-    return Out(vec4<f32>(x, y, 0., 1.), rgb);
+    return Out(to_clip_space(x, y), rgb);
 }
 
 @fragment
@@ -24,6 +38,48 @@ fn fragment_main(@location(0) cell: vec3<f32>) -> @location(0) vec4<f32> {
     return vec4<f32>(cell, 1.0);
 }
 
+// Cells packed 32 to a word, see `CellStorage::Bits`, which are rendered by looking up
+// the cell of each pixel instead of drawing an instance per cell:
+@binding(0) @group(1) var<storage, read> packed_cells: array<u32>;
+
+struct PackedOut {
+  @builtin(position) pos: vec4<f32>,
+  @location(0) clip: vec2<f32>,
+}
+
+// A triangle covering the whole viewport.
+@vertex
+fn packed_vertex_main(@builtin(vertex_index) i: u32) -> PackedOut {
+    let clip = vec2<f32>(f32(i % 2u) * 4. - 1., f32(i / 2u) * 4. - 1.);
+    return PackedOut(vec4<f32>(clip, 0., 1.), clip);
+}
+
+@fragment
+fn packed_fragment_main(@location(0) clip: vec2<f32>) -> @location(0) vec4<f32> {
+    // The inverse of `to_clip_space()`:
+    let grid_position = camera.center + clip / (2. * camera.scale);
+    if any(grid_position < vec2<f32>(0., 0.)) || any(grid_position >= vec2<f32>(1., 1.)) {
+        return vec4<f32>(0., 0., 0., 1.);
+    }
+    let cell = min(vec2<u32>(grid_position * vec2<f32>(size)), size - 1u);
+    let word = packed_cells[cell.y * ((size.x + 31u) / 32u) + cell.x / 32u];
+    let rgb = select(vec3(0., 0., 0.), spectral_bruton(1.), ((word >> (cell.x % 32u)) & 1u) == 1u);
+    return vec4<f32>(rgb, 1.);
+}
+
+@vertex
+fn selection_vertex_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    // Line strip around the selection, starting and ending at the bottom left corner:
+    var corner_x = array(0u, 1u, 1u, 0u, 0u);
+    var corner_y = array(0u, 0u, 1u, 1u, 0u);
+    return to_clip_space(selection.x + corner_x[i] * selection.z, selection.y + corner_y[i] * selection.w);
+}
+
+@fragment
+fn selection_fragment_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1., 1., 1., 1.);
+}
+
 fn spectral_bruton(w: f32) -> vec3<f32> {
     if w < 0.15 {
         return vec3<f32>(0.0, 1.0, -(w - 0.325) / (0.325 - 0.275));
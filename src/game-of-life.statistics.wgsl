@@ -0,0 +1,112 @@
+// Counts live cells, and the cells born and died since the previous generation, and
+// hashes the live cells to compare generations. Each grid of a batch, stored one after
+// the other, is counted by its own row of workgroups along the z dimension.
+
+struct Statistics {
+    population: atomic<u32>,
+    births: atomic<u32>,
+    deaths: atomic<u32>,
+    // The two halves of a 64-bit hash, summed over the positions of live cells so that
+    // the order of adding does not matter:
+    hash_low: atomic<u32>,
+    hash_high: atomic<u32>,
+}
+
+@group(0) @binding(0) var<storage, read> current: array<u32>;
+@group(0) @binding(1) var<storage, read> previous: array<u32>;
+@group(0) @binding(2) var<storage, read_write> statistics: array<Statistics>;
+@group(0) @binding(3) var<uniform> size: vec2<u32>;
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+// Summed within each workgroup first, to add to the result once per workgroup:
+var<workgroup> workgroup_statistics: Statistics;
+
+// The lowbias32 integer hash by Chris Wellons.
+fn hash(value: u32) -> u32 {
+    var h = value;
+    h ^= h >> 16u;
+    h *= 0x7feb352du;
+    h ^= h >> 15u;
+    h *= 0x846ca68bu;
+    h ^= h >> 16u;
+    return h;
+}
+
+// The hash of a live cell at an index counted in rows of cells, the same for either storage.
+fn cell_hash(index: u32) -> vec2<u32> {
+    let low = hash(index);
+    return vec2<u32>(low, hash(low ^ 0x9e3779b9u));
+}
+
+fn add_statistics(grid: u32, population: u32, births: u32, deaths: u32, cells_hash: vec2<u32>, local_index: u32) {
+    atomicAdd(&workgroup_statistics.population, population);
+    atomicAdd(&workgroup_statistics.births, births);
+    atomicAdd(&workgroup_statistics.deaths, deaths);
+    atomicAdd(&workgroup_statistics.hash_low, cells_hash.x);
+    atomicAdd(&workgroup_statistics.hash_high, cells_hash.y);
+    workgroupBarrier();
+    if local_index == 0u {
+        atomicAdd(&statistics[grid].population, atomicLoad(&workgroup_statistics.population));
+        atomicAdd(&statistics[grid].births, atomicLoad(&workgroup_statistics.births));
+        atomicAdd(&statistics[grid].deaths, atomicLoad(&workgroup_statistics.deaths));
+        atomicAdd(&statistics[grid].hash_low, atomicLoad(&workgroup_statistics.hash_low));
+        atomicAdd(&statistics[grid].hash_high, atomicLoad(&workgroup_statistics.hash_high));
+    }
+}
+
+// Each invocation strides over the cells, as large grids have more cells than can be dispatched.
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    var population = 0u;
+    var births = 0u;
+    var deaths = 0u;
+    var cells_hash = vec2<u32>(0u);
+    let grid_words = arrayLength(&current) / workgroups.z;
+    let grid_offset = id.z * grid_words;
+    for (var i = id.x; i < grid_words; i += workgroups.x * WORKGROUP_SIZE) {
+        let alive = current[grid_offset + i] > 0u;
+        let was_alive = previous[grid_offset + i] > 0u;
+        population += u32(alive);
+        births += u32(alive && !was_alive);
+        deaths += u32(was_alive && !alive);
+        if alive {
+            cells_hash += cell_hash(i);
+        }
+    }
+    add_statistics(id.z, population, births, deaths, cells_hash, local_index);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn main_bits(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let words_per_row = (size.x + 31u) / 32u;
+    var population = 0u;
+    var births = 0u;
+    var deaths = 0u;
+    var cells_hash = vec2<u32>(0u);
+    let grid_words = arrayLength(&current) / workgroups.z;
+    let grid_offset = id.z * grid_words;
+    for (var i = id.x; i < grid_words; i += workgroups.x * WORKGROUP_SIZE) {
+        // The last word of a row may extend outside the grid:
+        let remaining_bits = size.x - i % words_per_row * 32u;
+        let mask = select(~0u, (1u << remaining_bits) - 1u, remaining_bits < 32u);
+        let alive = current[grid_offset + i] & mask;
+        let was_alive = previous[grid_offset + i] & mask;
+        population += countOneBits(alive);
+        births += countOneBits(alive & ~was_alive);
+        deaths += countOneBits(was_alive & ~alive);
+        let first_index = i / words_per_row * size.x + i % words_per_row * 32u;
+        for (var bits = alive; bits != 0u; bits &= bits - 1u) {
+            cells_hash += cell_hash(first_index + firstTrailingBit(bits));
+        }
+    }
+    add_statistics(id.z, population, births, deaths, cells_hash, local_index);
+}
@@ -0,0 +1,43 @@
+// Included by the compute shaders, which declare the `size: vec2<u32>` and
+// `topology: vec4<u32>` uniforms used here. The topology is
+// (is_plane, is_sphere, top_bottom_twisted, left_right_twisted), see `Topology::uniform_array()`.
+
+// Only takes the remainder of non-negative values, as that is not reliable
+// for negative values on all platforms.
+fn modulo_euclidean(a: i32, b: i32) -> i32 {
+    return select(a % b, b - 1 - (-a - 1) % b, a < 0);
+}
+
+// Maps a cell up to one step outside the grid to the cell it is joined to, like
+// `Topology::cell_at()` does. Returns (x, y, exists), where `exists` is 0 for cells
+// outside the grid that are always dead.
+fn topology_cell(x: i32, y: i32) -> vec3<i32> {
+    let w = i32(size.x);
+    let h = i32(size.y);
+    let outside_x = x < 0 || x >= w;
+    let outside_y = y < 0 || y >= h;
+    if !outside_x && !outside_y {
+        return vec3<i32>(x, y, 1);
+    }
+
+    let is_plane = topology.x == 1u;
+    let is_sphere = topology.y == 1u;
+    let top_bottom_twisted = topology.z == 1u;
+    let left_right_twisted = topology.w == 1u;
+    if is_plane || (outside_x && outside_y && (is_sphere || (top_bottom_twisted && left_right_twisted))) {
+        return vec3<i32>(0, 0, 0);
+    }
+    if is_sphere {
+        if y >= h {
+            return vec3<i32>(0, h - 1 - x, 1);
+        } else if x < 0 {
+            return vec3<i32>(h - 1 - y, h - 1, 1);
+        } else if y < 0 {
+            return vec3<i32>(w - 1, h - 1 - x, 1);
+        }
+        return vec3<i32>(h - 1 - y, 0, 1);
+    }
+    let source_x = select(x, w - 1 - x, outside_y && top_bottom_twisted);
+    let source_y = select(y, h - 1 - y, outside_x && left_right_twisted);
+    return vec3<i32>(modulo_euclidean(source_x, w), modulo_euclidean(source_y, h), 1);
+}
@@ -0,0 +1,92 @@
+// Maps each destination cell to the source cell at `matrix * destination + offset`,
+// wrapping around the edges of the source grid.
+struct Transform {
+    matrix: vec4<i32>,
+    offset: vec2<i32>,
+    source_size: vec2<u32>,
+    destination_size: vec2<u32>,
+}
+
+@binding(0) @group(0) var<storage, read> source: array<u32>;
+@binding(1) @group(0) var<storage, read_write> destination: array<u32>;
+@binding(2) @group(0) var<uniform> transform: Transform;
+
+@binding(0) @group(1) var<storage, read> cells: array<u32>;
+@binding(1) @group(1) var<storage, read_write> bounds: array<atomic<u32>, 4>;
+@binding(2) @group(1) var<uniform> size: vec2<u32>;
+
+// Only takes the remainder of non-negative values, as that is not reliable
+// for negative values on all platforms.
+fn modulo_euclidean(a: i32, b: i32) -> i32 {
+    return select(a % b, b - 1 - (-a - 1) % b, a < 0);
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) grid: vec3<u32>) {
+    if grid.x >= transform.destination_size.x || grid.y >= transform.destination_size.y {
+        return;
+    }
+    let x = i32(grid.x);
+    let y = i32(grid.y);
+    let m = transform.matrix;
+    let source_x = modulo_euclidean(m.x * x + m.y * y + transform.offset.x, i32(transform.source_size.x));
+    let source_y = modulo_euclidean(m.z * x + m.w * y + transform.offset.y, i32(transform.source_size.y));
+    let source_index = u32(source_y) * transform.source_size.x + u32(source_x);
+    destination[grid.y * transform.destination_size.x + grid.x] = source[source_index];
+}
+
+// Computes the bounding box of live cells as (min_x, min_y, max_x, max_y).
+// The bounds are expected to be initialized to (u32::MAX, u32::MAX, 0, 0).
+@compute @workgroup_size(8, 8)
+fn bounding_box(@builtin(global_invocation_id) grid: vec3<u32>) {
+    if grid.x >= size.x || grid.y >= size.y || cells[grid.y * size.x + grid.x] == 0u {
+        return;
+    }
+    atomicMin(&bounds[0], grid.x);
+    atomicMin(&bounds[1], grid.y);
+    atomicMax(&bounds[2], grid.x);
+    atomicMax(&bounds[3], grid.y);
+}
+
+fn source_bit(x: i32, y: i32) -> u32 {
+    let m = transform.matrix;
+    let source_x = u32(modulo_euclidean(m.x * x + m.y * y + transform.offset.x, i32(transform.source_size.x)));
+    let source_y = u32(modulo_euclidean(m.z * x + m.w * y + transform.offset.y, i32(transform.source_size.y)));
+    let source_index = source_y * ((transform.source_size.x + 31u) / 32u) + source_x / 32u;
+    return (source[source_index] >> (source_x % 32u)) & 1u;
+}
+
+// Like `main` for cells packed 32 to a word, computing one destination word per invocation.
+@compute @workgroup_size(8, 8)
+fn main_bits(@builtin(global_invocation_id) grid: vec3<u32>) {
+    let words_per_row = (transform.destination_size.x + 31u) / 32u;
+    if grid.x >= words_per_row || grid.y >= transform.destination_size.y {
+        return;
+    }
+    var word = 0u;
+    for (var bit = 0u; bit < 32u; bit++) {
+        let x = grid.x * 32u + bit;
+        if x >= transform.destination_size.x {
+            break;
+        }
+        word |= source_bit(i32(x), i32(grid.y)) << bit;
+    }
+    destination[grid.y * words_per_row + grid.x] = word;
+}
+
+// Like `bounding_box` for cells packed 32 to a word, with one word per invocation.
+@compute @workgroup_size(8, 8)
+fn bounding_box_bits(@builtin(global_invocation_id) grid: vec3<u32>) {
+    let words_per_row = (size.x + 31u) / 32u;
+    if grid.x >= words_per_row || grid.y >= size.y {
+        return;
+    }
+    let word = cells[grid.y * words_per_row + grid.x];
+    if word == 0u {
+        return;
+    }
+    atomicMin(&bounds[0], grid.x * 32u + countTrailingZeros(word));
+    atomicMin(&bounds[1], grid.y);
+    atomicMax(&bounds[2], grid.x * 32u + 31u - countLeadingZeros(word));
+    atomicMax(&bounds[3], grid.y);
+}
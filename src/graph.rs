@@ -0,0 +1,263 @@
+//! A scrolling graph of the population over time, drawn in a corner over the grid.
+
+use std::collections::VecDeque;
+
+use wgpu::util::DeviceExt;
+
+/// The number of samples shown, one for each time statistics were read back.
+const CAPACITY: usize = 256;
+
+pub(crate) struct PopulationGraph {
+    background_pipeline: wgpu::RenderPipeline,
+    line_pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    values_buffer: wgpu::Buffer,
+    graph_buffer: wgpu::Buffer,
+    /// The generation and population of samples, oldest first.
+    samples: VecDeque<(u64, u32)>,
+    pub(crate) log_scale: bool,
+}
+
+impl PopulationGraph {
+    pub(crate) fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("game-of-life.graph.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("graph_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("graph_pipeline_layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        });
+        let create_pipeline = |label, entry_point_prefix: &str, topology, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    buffers: &[],
+                    entry_point: Some(&format!("{entry_point_prefix}_vertex_main")),
+                    module: &shader,
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    entry_point: Some(&format!("{entry_point_prefix}_fragment_main")),
+                    module: &shader,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: texture_format,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: Default::default(),
+            })
+        };
+        let background_pipeline = create_pipeline(
+            "graph_background_pipeline",
+            "background",
+            wgpu::PrimitiveTopology::TriangleStrip,
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+        );
+        let line_pipeline = create_pipeline(
+            "graph_line_pipeline",
+            "line",
+            wgpu::PrimitiveTopology::LineStrip,
+            None,
+        );
+
+        let values_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("graph_values_buffer"),
+            size: (CAPACITY * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let graph_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("graph_buffer"),
+            contents: bytemuck::cast_slice(&[0, CAPACITY as u32]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: values_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: graph_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("graph_bind_group"),
+        });
+
+        Self {
+            background_pipeline,
+            line_pipeline,
+            bind_group,
+            values_buffer,
+            graph_buffer,
+            samples: VecDeque::with_capacity(CAPACITY),
+            log_scale: false,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Add the population at a generation, which is ignored if the generation has
+    /// not changed, and starts over if it went backwards.
+    pub(crate) fn record(&mut self, generation: u64, population: u32) {
+        match self.samples.back() {
+            Some(&(latest_generation, _)) if generation == latest_generation => return,
+            Some(&(latest_generation, _)) if generation < latest_generation => self.clear(),
+            _ => {}
+        }
+        if self.samples.len() == CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((generation, population));
+    }
+
+    /// The samples scaled to be between 0 and 1, relative to the largest one.
+    fn values(&self) -> Vec<f32> {
+        let scale = |population: u32| {
+            if self.log_scale {
+                (f64::from(population)).ln_1p()
+            } else {
+                f64::from(population)
+            }
+        };
+        let max = self
+            .samples
+            .iter()
+            .map(|&(_, population)| scale(population))
+            .fold(0., f64::max);
+        self.samples
+            .iter()
+            .map(|&(_, population)| {
+                if max > 0. {
+                    (scale(population) / max) as f32
+                } else {
+                    0.
+                }
+            })
+            .collect()
+    }
+
+    /// Draw the graph over the surface texture rendered to earlier in the encoder.
+    pub(crate) fn enqueue(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_texture: &wgpu::SurfaceTexture,
+        texture_view_descriptor: &wgpu::TextureViewDescriptor,
+    ) {
+        let values = self.values();
+        queue.write_buffer(&self.values_buffer, 0, bytemuck::cast_slice(&values));
+        queue.write_buffer(
+            &self.graph_buffer,
+            0,
+            bytemuck::cast_slice(&[values.len() as u32]),
+        );
+
+        let view = surface_texture.texture.create_view(texture_view_descriptor);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("graph_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            ..Default::default()
+        });
+
+        // In the bottom right corner, a third of the width of the surface:
+        let (surface_width, surface_height) = (
+            surface_texture.texture.width() as f32,
+            surface_texture.texture.height() as f32,
+        );
+        let margin = (surface_width.min(surface_height) * 0.02).round();
+        let width = (surface_width / 3.).round();
+        let height = (width * 0.4).min(surface_height - 2. * margin).round();
+        render_pass.set_viewport(
+            surface_width - width - margin,
+            surface_height - height - margin,
+            width,
+            height,
+            0.,
+            1.,
+        );
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_pipeline(&self.background_pipeline);
+        render_pass.draw(0..4, 0..1);
+        if values.len() > 1 {
+            render_pass.set_pipeline(&self.line_pipeline);
+            render_pass.draw(0..values.len() as u32, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_values() {
+        let (device, _queue) = testing::device();
+        let mut graph = PopulationGraph::new(&device, wgpu::TextureFormat::Rgba8UnormSrgb);
+
+        for (generation, population) in [(0, 0), (1, 100), (1, 50), (3, 1000)] {
+            graph.record(generation, population);
+        }
+        assert_eq!(graph.values(), [0., 0.1, 1.]);
+        graph.log_scale = true;
+        let values = graph.values();
+        assert!((values[1] - 101_f32.ln() / 1001_f32.ln()).abs() < 1e-6);
+
+        // Rewinding starts over:
+        graph.record(2, 10);
+        assert_eq!(graph.values(), [1.]);
+
+        for generation in 3..1000 {
+            graph.record(generation, 1);
+        }
+        assert_eq!(graph.values().len(), CAPACITY);
+    }
+}
@@ -0,0 +1,699 @@
+//! [HashLife](https://conwaylife.com/wiki/HashLife) on an unbounded plane, which advances
+//! patterns with regularity in space and time, such as guns and breeders, by huge numbers
+//! of generations.
+//!
+//! The plane is a quadtree where equal nodes are shared, and the centre of each node
+//! advanced by a power of two generations is memoised. A window of the plane is laid out
+//! as in the cell buffers to be shown by a [`crate::simulation::Simulation`].
+
+use std::collections::HashMap;
+
+use crate::computer::CellStorage;
+use crate::rle;
+use crate::rules;
+
+/// The index of a node in [`HashLife::nodes`].
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// The level of the root before the pattern grows.
+const MIN_LEVEL: u8 = 3;
+
+/// The largest level of the root, keeping the coordinates of its cells within `i64`.
+const MAX_LEVEL: u8 = 62;
+
+/// The number of nodes above which the ones no longer part of the plane are dropped,
+/// along with the memoised results.
+const MAX_NODES: usize = 1 << 24;
+
+#[derive(Clone, Copy)]
+struct Node {
+    /// The lower left, lower right, upper left and upper right quadrants, which are
+    /// [`DEAD`] for single cells.
+    children: [NodeId; 4],
+    /// The node covers 2^level x 2^level cells.
+    level: u8,
+    /// The number of live cells, saturating at `u64::MAX`.
+    population: u64,
+}
+
+pub struct HashLife {
+    born: u16,
+    survives: u16,
+    /// All nodes, starting with a dead and a live cell.
+    nodes: Vec<Node>,
+    /// The node with the given quadrants.
+    ids: HashMap<[NodeId; 4], NodeId>,
+    /// The empty node of each level.
+    empty: Vec<NodeId>,
+    /// The centre of a node advanced by 2^k generations, by the node and k.
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// The plane, centred at `(0, 0)` so that it covers `-2^(level-1)..2^(level-1)` along
+    /// both axes, with all cells outside dead.
+    root: NodeId,
+    generation: u64,
+}
+
+impl HashLife {
+    /// An empty plane for a Life-like rule, which must not have births on 0 neighbours
+    /// as that would make the infinitely many cells outside the pattern alive.
+    pub fn new(born: u16, survives: u16) -> Result<Self, String> {
+        if born & 1 != 0 {
+            return Err(format!(
+                "HashLife does not support {}, where empty cells are born",
+                rules::rulestring(born, survives)
+            ));
+        }
+        let cell = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        let mut hashlife = Self {
+            born,
+            survives,
+            nodes: vec![cell(0), cell(1)],
+            ids: HashMap::new(),
+            empty: vec![DEAD],
+            results: HashMap::new(),
+            root: DEAD,
+            generation: 0,
+        };
+        hashlife.root = hashlife.empty(MIN_LEVEL);
+        Ok(hashlife)
+    }
+
+    /// Load a pattern in the [RLE](https://conwaylife.com/wiki/Run_Length_Encoded) format,
+    /// in Conway's Life if the header has no rule, with the top left cell at `(0, 0)` and
+    /// the following rows at decreasing `y`.
+    ///
+    /// Bounded grid suffixes of the rule are ignored, as the plane is unbounded.
+    pub fn from_rle(text: &str) -> Result<Self, String> {
+        let pattern = rle::decode(text)?;
+        let rule = rules::parse_rulestring(pattern.rulestring.as_deref().unwrap_or("B3/S23"))?;
+        let mut hashlife = Self::new(rule.born, rule.survives)?;
+        for (x, row) in pattern.cells {
+            hashlife.set_cell(i64::from(x), -i64::from(row), true)?;
+        }
+        Ok(hashlife)
+    }
+
+    /// Load a pattern in Golly's two-state
+    /// [macrocell](https://conwaylife.com/wiki/Macrocell) format, where the last node is
+    /// centred at `(0, 0)`.
+    pub fn from_macrocell(macrocell: &str) -> Result<Self, String> {
+        let mut lines = macrocell.lines().map(str::trim);
+        if !lines.next().is_some_and(|line| line.starts_with("[M2]")) {
+            return Err("Not a macrocell pattern".to_string());
+        }
+        let lines: Vec<&str> = lines.filter(|line| !line.is_empty()).collect();
+
+        let mut rulestring = "B3/S23";
+        let mut generation = 0;
+        for line in &lines {
+            if let Some(rule) = line.strip_prefix("#R") {
+                rulestring = rule.trim();
+            } else if let Some(value) = line.strip_prefix("#G") {
+                generation = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid generation in macrocell: '{line}'"))?;
+            }
+        }
+        let rule = rules::parse_rulestring(rulestring)?;
+        let mut hashlife = Self::new(rule.born, rule.survives)?;
+        hashlife.generation = generation;
+
+        // The nodes of the lines, which refer to earlier ones counting from 1:
+        let mut macrocell_nodes: Vec<NodeId> = Vec::new();
+        for line in lines.into_iter().filter(|line| !line.starts_with('#')) {
+            let invalid = || format!("Invalid macrocell line: '{line}'");
+            let node = if line.starts_with(['.', '*', '$']) {
+                // An 8x8 leaf with rows from the top, ending with '$' and omitting trailing dead cells:
+                let mut rows = [0_u8; 8];
+                for (row_index, row) in line.split('$').take(8).enumerate() {
+                    for (x, c) in row.chars().enumerate() {
+                        match c {
+                            '*' if x < 8 => rows[7 - row_index] |= 1 << x,
+                            '.' if x < 8 => {}
+                            _ => return Err(invalid()),
+                        }
+                    }
+                }
+                hashlife.node_from_rows(rows, 0, 0, 3)
+            } else {
+                let numbers: Vec<usize> = line
+                    .split_whitespace()
+                    .map(|number| number.parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                let [level, nw, ne, sw, se] = numbers[..] else {
+                    return Err(invalid());
+                };
+                if !(4..=usize::from(MAX_LEVEL)).contains(&level) {
+                    return Err(invalid());
+                }
+                let level = level as u8;
+                let mut child = |index: usize| match index {
+                    0 => Ok(hashlife.empty(level - 1)),
+                    _ => macrocell_nodes
+                        .get(index - 1)
+                        .copied()
+                        .filter(|&child| hashlife.nodes[child as usize].level == level - 1)
+                        .ok_or_else(invalid),
+                };
+                let children = [child(sw)?, child(se)?, child(nw)?, child(ne)?];
+                hashlife.node(children)
+            };
+            macrocell_nodes.push(node);
+        }
+        if let Some(&root) = macrocell_nodes.last() {
+            hashlife.root = root;
+        }
+        Ok(hashlife)
+    }
+
+    /// The rule in B/S notation.
+    pub fn rulestring(&self) -> String {
+        rules::rulestring(self.born, self.survives)
+    }
+
+    /// The number of generations advanced since the pattern was loaded, or the generation
+    /// given by a macrocell pattern.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The number of live cells, saturating at `u64::MAX`.
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    /// Half the width of the plane, which covers `-half..half` along both axes.
+    fn half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        (-half..half).contains(&x) && (-half..half).contains(&y)
+    }
+
+    pub fn is_alive(&self, x: i64, y: i64) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+        let half = self.half();
+        let (mut x, mut y) = ((x + half) as u64, (y + half) as u64);
+        let mut id = self.root;
+        while self.level(id) > 0 {
+            let half = 1 << (self.level(id) - 1);
+            id = self.children(id)[usize::from(y >= half) * 2 + usize::from(x >= half)];
+            (x, y) = (x % half, y % half);
+        }
+        id == ALIVE
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<(), String> {
+        while !self.contains(x, y) {
+            self.expand()?;
+        }
+        let half = self.half();
+        self.root = self.set_in(self.root, (x + half) as u64, (y + half) as u64, alive);
+        Ok(())
+    }
+
+    /// Set the cell at `(x, y)` counting from the lower left corner of a node.
+    fn set_in(&mut self, id: NodeId, x: u64, y: u64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let index = usize::from(y >= half) * 2 + usize::from(x >= half);
+        let mut children = self.children(id);
+        children[index] = self.set_in(children[index], x % half, y % half, alive);
+        self.node(children)
+    }
+
+    /// The node of the given quadrants, shared with any equal node.
+    fn node(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.level(children[0]) + 1,
+            population: children.iter().fold(0, |population, &child| {
+                population.saturating_add(self.nodes[child as usize].population)
+            }),
+        });
+        self.ids.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= usize::from(level) {
+            let smaller = self.empty[self.empty.len() - 1];
+            let empty = self.node([smaller; 4]);
+            self.empty.push(empty);
+        }
+        self.empty[usize::from(level)]
+    }
+
+    /// A node of the 8x8 cells of `rows`, where bit x of `rows[y]` is the cell at `(x, y)`.
+    fn node_from_rows(&mut self, rows: [u8; 8], x: usize, y: usize, level: u8) -> NodeId {
+        if level == 0 {
+            return NodeId::from((rows[y] >> x) & 1);
+        }
+        let half = 1 << (level - 1);
+        let children = [(0, 0), (half, 0), (0, half), (half, half)]
+            .map(|(dx, dy)| self.node_from_rows(rows, x + dx, y + dy, level - 1));
+        self.node(children)
+    }
+
+    /// The middle half of a node, one level smaller.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [lower_left, lower_right, upper_left, upper_right] = self.children(id);
+        self.node([
+            self.children(lower_left)[3],
+            self.children(lower_right)[2],
+            self.children(upper_left)[1],
+            self.children(upper_right)[0],
+        ])
+    }
+
+    /// Double the width of the plane, keeping it centred at `(0, 0)`.
+    fn expand(&mut self) -> Result<(), String> {
+        let level = self.level(self.root);
+        if level >= MAX_LEVEL {
+            return Err(format!(
+                "HashLife does not support patterns wider than 2^{MAX_LEVEL} cells"
+            ));
+        }
+        let empty = self.empty(level - 1);
+        let [lower_left, lower_right, upper_left, upper_right] = self.children(self.root);
+        let children = [
+            [empty, empty, empty, lower_left],
+            [empty, empty, lower_right, empty],
+            [empty, upper_left, empty, empty],
+            [upper_right, empty, empty, empty],
+        ]
+        .map(|children| self.node(children));
+        self.root = self.node(children);
+        Ok(())
+    }
+
+    /// Advance by 2^`log2_generations` generations.
+    pub fn step(&mut self, log2_generations: u8) -> Result<(), String> {
+        let generation = 1_u64
+            .checked_shl(u32::from(log2_generations))
+            .and_then(|generations| self.generation.checked_add(generations))
+            .ok_or_else(|| "HashLife does not support more than 2^64 generations".to_string())?;
+
+        // Cells travel at most one cell per generation, so a pattern in the middle quarter
+        // stays within the middle half that is advanced:
+        loop {
+            if self.level(self.root) >= log2_generations.saturating_add(3) {
+                let centre = self.centre(self.root);
+                let middle_quarter = self.centre(centre);
+                if self.nodes[middle_quarter as usize].population == self.population() {
+                    break;
+                }
+            }
+            self.expand()?;
+        }
+        self.root = self.successor(self.root, log2_generations);
+        self.generation = generation;
+
+        while self.level(self.root) > MIN_LEVEL {
+            let centre = self.centre(self.root);
+            if self.nodes[centre as usize].population != self.population() {
+                break;
+            }
+            self.root = centre;
+        }
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+        Ok(())
+    }
+
+    /// The middle half of a node of at least level 2 advanced by 2^k generations,
+    /// where k is at most the level minus 2.
+    fn successor(&mut self, id: NodeId, k: u8) -> NodeId {
+        let level = self.level(id);
+        if self.nodes[id as usize].population == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, k)) {
+            return result;
+        }
+
+        // The 4x4 nodes two levels smaller, from the lower left:
+        let grandchildren: [[NodeId; 4]; 4] = std::array::from_fn(|y| {
+            std::array::from_fn(|x| {
+                self.children(self.children(id)[y / 2 * 2 + x / 2])[y % 2 * 2 + x % 2]
+            })
+        });
+        let result = if level == 2 {
+            self.next_generation_of_cells(grandchildren)
+        } else {
+            // Advancing the 3x3 overlapping nodes one level smaller, and then the 2x2 nodes
+            // put together from their results, advances by 2^(level - 2) generations in total.
+            // Smaller steps take the centres of the 3x3 nodes instead of advancing them.
+            let full_step = k == level - 2;
+            let mut intermediate = [[DEAD; 3]; 3];
+            for y in 0..3 {
+                for x in 0..3 {
+                    let node = self.node([
+                        grandchildren[y][x],
+                        grandchildren[y][x + 1],
+                        grandchildren[y + 1][x],
+                        grandchildren[y + 1][x + 1],
+                    ]);
+                    intermediate[y][x] = if full_step {
+                        self.successor(node, k - 1)
+                    } else {
+                        self.centre(node)
+                    };
+                }
+            }
+            let k = if full_step { k - 1 } else { k };
+            let children = [(0, 0), (0, 1), (1, 0), (1, 1)].map(|(y, x)| {
+                let node = self.node([
+                    intermediate[y][x],
+                    intermediate[y][x + 1],
+                    intermediate[y + 1][x],
+                    intermediate[y + 1][x + 1],
+                ]);
+                self.successor(node, k)
+            });
+            self.node(children)
+        };
+        self.results.insert((id, k), result);
+        result
+    }
+
+    /// The middle 2x2 cells of 4x4 cells advanced by a generation.
+    fn next_generation_of_cells(&mut self, cells: [[NodeId; 4]; 4]) -> NodeId {
+        let next_state = |x: usize, y: usize| {
+            let neighbours = (y - 1..=y + 1)
+                .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                .filter(|&(nx, ny)| (nx, ny) != (x, y) && cells[ny][nx] == ALIVE)
+                .count();
+            let rule_mask = if cells[y][x] == ALIVE {
+                self.survives
+            } else {
+                self.born
+            };
+            NodeId::from((rule_mask >> neighbours) & 1)
+        };
+        let children = [(1, 1), (2, 1), (1, 2), (2, 2)].map(|(x, y)| next_state(x, y));
+        self.node(children)
+    }
+
+    /// Drop the nodes that are no longer part of the plane, and the memoised results.
+    fn collect_garbage(&mut self) {
+        let Ok(mut kept) = Self::new(self.born, self.survives) else {
+            return;
+        };
+        let mut copies = HashMap::from([(DEAD, DEAD), (ALIVE, ALIVE)]);
+        kept.root = kept.copy_node(self, self.root, &mut copies);
+        kept.generation = self.generation;
+        *self = kept;
+    }
+
+    fn copy_node(
+        &mut self,
+        other: &Self,
+        id: NodeId,
+        copies: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if let Some(&copy) = copies.get(&id) {
+            return copy;
+        }
+        let children = other
+            .children(id)
+            .map(|child| self.copy_node(other, child, copies));
+        let copy = self.node(children);
+        copies.insert(id, copy);
+        copy
+    }
+
+    /// The smallest rectangle holding all live cells, as `(left, bottom, right, top)`
+    /// including the cells at the edges, or `None` if there are no live cells.
+    pub fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        let half = self.half();
+        let width = 2 * half - 1;
+        // The quadrants nearest to each edge, and the ones further away:
+        let distance = |near, far| {
+            self.edge_distance(self.root, near, far, &mut HashMap::new())
+                .map(|distance| distance as i64)
+        };
+        Some((
+            distance([0, 2], [1, 3])? - half,
+            distance([0, 1], [2, 3])? - half,
+            width - distance([1, 3], [0, 2])? - half,
+            width - distance([2, 3], [0, 1])? - half,
+        ))
+    }
+
+    /// The distance from an edge of a node to its nearest live cell, where `near` are
+    /// the quadrants along that edge and `far` the other ones.
+    fn edge_distance(
+        &self,
+        id: NodeId,
+        near: [usize; 2],
+        far: [usize; 2],
+        distances: &mut HashMap<NodeId, Option<u64>>,
+    ) -> Option<u64> {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some(0);
+        }
+        if let Some(&distance) = distances.get(&id) {
+            return distance;
+        }
+        let mut nearest = |quadrants: [usize; 2]| {
+            quadrants
+                .iter()
+                .filter_map(|&quadrant| {
+                    self.edge_distance(node.children[quadrant], near, far, distances)
+                })
+                .min()
+        };
+        let distance = nearest(near)
+            .or_else(|| nearest(far).map(|distance| distance + (1 << (node.level - 1))));
+        distances.insert(id, distance);
+        distance
+    }
+
+    /// The cells of the window with its lower left cell at `(left, bottom)`, laid out as
+    /// in the cell buffers, with an age of 1 for all live cells.
+    pub fn window_words(
+        &self,
+        left: i64,
+        bottom: i64,
+        cells_width: u32,
+        cells_height: u32,
+        storage: CellStorage,
+    ) -> Vec<u32> {
+        let mut words = vec![0; (storage.buffer_size(cells_width, cells_height) / 4) as usize];
+        let words_per_row = storage.words_per_row(cells_width) as usize;
+        let window = (
+            left,
+            bottom,
+            left.saturating_add(i64::from(cells_width)),
+            bottom.saturating_add(i64::from(cells_height)),
+        );
+        let half = self.half();
+        self.visit_live_cells(self.root, -half, -half, window, &mut |x, y| {
+            let (x, y) = ((x - left) as usize, (y - bottom) as usize);
+            match storage {
+                CellStorage::Ages => words[y * words_per_row + x] = 1,
+                CellStorage::Bits => words[y * words_per_row + x / 32] |= 1 << (x % 32),
+            }
+        });
+        words
+    }
+
+    /// Call `on_cell` for the live cells of a node with its lower left cell at `(x, y)`
+    /// that are within `(left, bottom, right, top)`, excluding the right and top edges.
+    fn visit_live_cells(
+        &self,
+        id: NodeId,
+        x: i64,
+        y: i64,
+        window: (i64, i64, i64, i64),
+        on_cell: &mut impl FnMut(i64, i64),
+    ) {
+        let node = self.nodes[id as usize];
+        let width = 1_i64 << node.level;
+        let (left, bottom, right, top) = window;
+        if node.population == 0
+            || x >= right
+            || y >= top
+            || x + width <= left
+            || y + width <= bottom
+        {
+            return;
+        }
+        if node.level == 0 {
+            on_cell(x, y);
+            return;
+        }
+        let half = width / 2;
+        for (child, (dx, dy)) in
+            node.children
+                .into_iter()
+                .zip([(0, 0), (half, 0), (0, half), (half, half)])
+        {
+            self.visit_live_cells(child, x + dx, y + dy, window, on_cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CpuSimulation;
+    use crate::selection::Rect;
+    use crate::simulation::Settings;
+    use crate::topology::Topology;
+
+    fn live_cells(hashlife: &HashLife) -> Vec<(i64, i64)> {
+        let (left, bottom, right, top) = hashlife.bounds().unwrap();
+        (bottom..=top)
+            .flat_map(|y| (left..=right).map(move |x| (x, y)))
+            .filter(|&(x, y)| hashlife.is_alive(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn test_cpu_equivalence() {
+        for rulestring in ["B3/S23", "B37/S23", "B3678/S34678"] {
+            let rule = rules::parse_rulestring(rulestring).unwrap();
+            let settings = Settings {
+                rule_idx: rules::RULES
+                    .iter()
+                    .position(|r| (r.born, r.survives) == (rule.born, rule.survives))
+                    .unwrap() as u32,
+                topology: Topology::Plane,
+                cells_width: 160,
+                cells_height: 128,
+                cell_storage: CellStorage::Bits,
+                ..Settings::default()
+            };
+            let mut cpu = CpuSimulation::new(settings).unwrap();
+            cpu.write_cells(
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 160,
+                    height: 128,
+                },
+                &vec![0; 160 * 128],
+            )
+            .unwrap();
+
+            // A soup in the middle, which does not reach the edges within 48 generations:
+            let mut hashlife = HashLife::new(rule.born, rule.survives).unwrap();
+            let (left, bottom) = (-80, -64);
+            for y in -8..8 {
+                for x in -8..8 {
+                    let alive = (x * 7 + y * 13 + x * y) % 3 == 0;
+                    hashlife.set_cell(x, y, alive).unwrap();
+                    let (x, y) = ((x - left) as u32, (y - bottom) as u32);
+                    cpu.write_cells(
+                        Rect {
+                            x,
+                            y,
+                            width: 1,
+                            height: 1,
+                        },
+                        &[u32::from(alive)],
+                    )
+                    .unwrap();
+                }
+            }
+
+            for log2_generations in [0, 0, 1, 3, 2, 5] {
+                hashlife.step(log2_generations).unwrap();
+                cpu.step(1 << log2_generations);
+                assert_eq!(hashlife.generation(), cpu.generation());
+                assert_eq!(
+                    hashlife.window_words(left, bottom, 160, 128, CellStorage::Bits),
+                    cpu.cells().into_words(),
+                    "{rulestring} at generation {}",
+                    hashlife.generation()
+                );
+                assert_eq!(
+                    hashlife.population(),
+                    u64::from(cpu.statistics().population)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_run() {
+        let mut hashlife = HashLife::from_rle("x = 3, y = 3, rule = B3/S23\nb2o$2o$bo!").unwrap();
+        assert_eq!(hashlife.population(), 5);
+        // The R-pentomino settles at generation 1103 with 116 cells, including 6 gliders
+        // travelling away:
+        hashlife.step(11).unwrap();
+        assert_eq!(hashlife.generation(), 2048);
+        assert_eq!(hashlife.population(), 116);
+        hashlife.step(40).unwrap();
+        assert_eq!(hashlife.generation(), 2048 + (1 << 40));
+        assert_eq!(hashlife.population(), 116);
+        let (left, bottom, right, top) = hashlife.bounds().unwrap();
+        assert!(right - left > 1 << 38 && top - bottom > 1 << 38);
+
+        assert!(HashLife::from_rle("x = 1, y = 1, rule = B03/S23\no!").is_err());
+        let mut hashlife = HashLife::from_rle("o!").unwrap();
+        assert!(hashlife.step(64).is_err());
+    }
+
+    #[test]
+    fn test_macrocell() {
+        let glider =
+            HashLife::from_macrocell("[M2] (golly 4.2)\n#R B3/S23\n.*$..*$***$\n").unwrap();
+        // The leaf is the whole plane, centred at (0, 0):
+        assert_eq!(
+            live_cells(&glider),
+            [(-4, 1), (-3, 1), (-2, 1), (-2, 2), (-3, 3)]
+        );
+
+        let mut hashlife = HashLife::from_macrocell(
+            "[M2] (golly 4.2)\n#R B3/S23\n#G 100\n.*$..*$***$\n4 1 0 0 0\n",
+        )
+        .unwrap();
+        assert_eq!(hashlife.generation(), 100);
+        // The glider is in the upper left quadrant, with the top left cell at (-8, 7):
+        let cells = live_cells(&hashlife);
+        assert_eq!(cells, [(-8, 5), (-7, 5), (-6, 5), (-6, 6), (-7, 7)]);
+        hashlife.step(2).unwrap();
+        assert_eq!(hashlife.generation(), 104);
+        let moved: Vec<(i64, i64)> = cells.iter().map(|&(x, y)| (x + 1, y - 1)).collect();
+        assert_eq!(live_cells(&hashlife), moved);
+
+        assert!(HashLife::from_macrocell("x = 1, y = 1\no!").is_err());
+        assert!(HashLife::from_macrocell("[M2]\n4 1 0 0 0\n").is_err());
+        assert!(HashLife::from_macrocell("[M2]\n.*$\n5 1 0 0 0\n").is_err());
+    }
+}
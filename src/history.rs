@@ -0,0 +1,337 @@
+//! Past generations kept as snapshots on the GPU, to step backwards and undo edits.
+//!
+//! Snapshots are taken periodically while advancing, and around edits of the cells.
+//! A generation is restored from the latest snapshot before it and computed forward from there.
+
+use std::collections::VecDeque;
+
+use crate::computer::Computer;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SnapshotKind {
+    Periodic,
+    /// Taken before cells were edited, to be restored when undoing the edits.
+    BeforeEdit,
+    /// Taken after cells were edited, as the edits cannot be computed forward.
+    AfterEdit,
+}
+
+struct Snapshot {
+    generation: u64,
+    kind: SnapshotKind,
+    buffer: wgpu::Buffer,
+}
+
+pub(crate) struct History {
+    /// In the order taken, and so also by generation.
+    snapshots: VecDeque<Snapshot>,
+    /// The number of generations between periodic snapshots.
+    cadence: u64,
+    /// The maximum number of bytes used by snapshot buffers.
+    memory_budget: u64,
+    /// Whether the cells have been edited since the latest snapshot.
+    edited: bool,
+}
+
+impl History {
+    pub(crate) fn new(cadence: u64, memory_budget: u64) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            cadence: cadence.max(1),
+            memory_budget,
+            edited: false,
+        }
+    }
+
+    pub(crate) fn set_limits(&mut self, cadence: u64, memory_budget: u64) {
+        self.cadence = cadence.max(1);
+        self.memory_budget = memory_budget;
+        if let Some(snapshot) = self.snapshots.front() {
+            let capacity = self.capacity(snapshot.buffer.size());
+            while self.snapshots.len() > capacity {
+                self.snapshots.pop_front();
+            }
+        }
+    }
+
+    /// Forget all snapshots, such as when the grid is replaced.
+    pub(crate) fn clear(&mut self) {
+        self.snapshots.clear();
+        self.edited = false;
+    }
+
+    /// The range of generations that can be restored, if any.
+    pub(crate) fn generations(&self) -> Option<(u64, u64)> {
+        Some((
+            self.snapshots.front()?.generation,
+            self.snapshots.back()?.generation,
+        ))
+    }
+
+    fn capacity(&self, buffer_size: u64) -> usize {
+        (self.memory_budget / buffer_size.max(1)) as usize
+    }
+
+    fn record(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        computer: &Computer,
+        generation: u64,
+        kind: SnapshotKind,
+    ) {
+        let source = computer.current_cells_buffer();
+        let capacity = self.capacity(source.size());
+        if capacity == 0 {
+            return;
+        }
+        let mut reused_buffer = None;
+        while self.snapshots.len() >= capacity {
+            reused_buffer = self.snapshots.pop_front().map(|snapshot| snapshot.buffer);
+        }
+        let buffer = reused_buffer
+            .filter(|buffer| buffer.size() == source.size())
+            .unwrap_or_else(|| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("snapshot_buffer"),
+                    size: source.size(),
+                    usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            });
+        encoder.copy_buffer_to_buffer(source, 0, &buffer, 0, source.size());
+        self.snapshots.push_back(Snapshot {
+            generation,
+            kind,
+            buffer,
+        });
+    }
+
+    /// Forget the snapshots after `generation`, which are no longer reached when
+    /// advancing or editing after having rewound to it.
+    fn truncate_after(&mut self, generation: u64) {
+        while self
+            .snapshots
+            .back()
+            .is_some_and(|snapshot| snapshot.generation > generation)
+        {
+            self.snapshots.pop_back();
+        }
+    }
+
+    /// Record the edits made since the latest snapshot, so that they are restored when
+    /// rewinding to a later generation.
+    fn record_edits(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        computer: &Computer,
+        generation: u64,
+    ) {
+        if self.edited {
+            self.record(
+                device,
+                encoder,
+                computer,
+                generation,
+                SnapshotKind::AfterEdit,
+            );
+            self.edited = false;
+        }
+    }
+
+    /// To be called before the cells at `generation` are edited.
+    pub(crate) fn before_edit(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        computer: &Computer,
+        generation: u64,
+    ) {
+        if self.edited {
+            return;
+        }
+        self.truncate_after(generation);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("snapshot_encoder"),
+        });
+        self.record(
+            device,
+            &mut encoder,
+            computer,
+            generation,
+            SnapshotKind::BeforeEdit,
+        );
+        // Submitted before the edits, which are written at the start of the next submission:
+        queue.submit(std::iter::once(encoder.finish()));
+        self.edited = true;
+    }
+
+    /// To be called with the encoder advancing from `generation`, before enqueueing that.
+    pub(crate) fn before_advance(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        computer: &Computer,
+        generation: u64,
+    ) {
+        self.truncate_after(generation);
+        if self.edited {
+            self.record(
+                device,
+                encoder,
+                computer,
+                generation,
+                SnapshotKind::AfterEdit,
+            );
+            self.edited = false;
+        } else if self
+            .snapshots
+            .back()
+            .is_none_or(|snapshot| generation >= snapshot.generation + self.cadence)
+        {
+            self.record(
+                device,
+                encoder,
+                computer,
+                generation,
+                SnapshotKind::Periodic,
+            );
+        }
+    }
+
+    /// Restore the latest snapshot at or before `generation` into the current cells,
+    /// returning its generation to compute forward from.
+    pub(crate) fn rewind(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        computer: &Computer,
+        current_generation: u64,
+        generation: u64,
+    ) -> Option<u64> {
+        self.record_edits(device, encoder, computer, current_generation);
+        let snapshot = self
+            .snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.generation <= generation)?;
+        let destination = computer.current_cells_buffer();
+        if snapshot.buffer.size() != destination.size() {
+            return None;
+        }
+        encoder.copy_buffer_to_buffer(&snapshot.buffer, 0, destination, 0, destination.size());
+        Some(snapshot.generation)
+    }
+
+    /// Restore the cells from before the latest edits, returning their generation.
+    pub(crate) fn undo(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        computer: &Computer,
+    ) -> Option<u64> {
+        let index = self
+            .snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.kind == SnapshotKind::BeforeEdit)?;
+        self.snapshots.truncate(index + 1);
+        let snapshot = self.snapshots.pop_back()?;
+        let destination = computer.current_cells_buffer();
+        if snapshot.buffer.size() != destination.size() {
+            return None;
+        }
+        encoder.copy_buffer_to_buffer(&snapshot.buffer, 0, destination, 0, destination.size());
+        self.edited = false;
+        Some(snapshot.generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::{CellStorage, ComputerFactory};
+    use crate::selection::Rect;
+    use crate::testing;
+    use crate::topology::Topology;
+
+    #[test]
+    fn test_rewind_and_undo() {
+        let (device, queue) = testing::device();
+        let factory = ComputerFactory::new(&device);
+        let rule = &crate::rules::RULES[0];
+
+        for storage in [CellStorage::Ages, CellStorage::Bits] {
+            let create_computer = || {
+                factory
+                    .create(&device, 64, 48, storage, rule, Topology::Torus, 1, 50)
+                    .unwrap()
+            };
+            let read_cells = |computer: &Computer| {
+                let cells = testing::read_cells(&device, &queue, computer);
+                (0..48)
+                    .flat_map(|y| (0..64).map(move |x| (x, y)))
+                    .map(|(x, y)| cells.get(x, y))
+                    .collect::<Vec<_>>()
+            };
+            let advance =
+                |computer: &mut Computer, history: &mut History, generation, generations| {
+                    let mut encoder = device.create_command_encoder(&Default::default());
+                    history.before_advance(&device, &mut encoder, computer, generation);
+                    computer.enqueue_generations(&mut encoder, generations);
+                    queue.submit(std::iter::once(encoder.finish()));
+                };
+
+            let mut computer = create_computer();
+            let mut history = History::new(10, 1 << 20);
+            for generation in (0..30).step_by(5) {
+                advance(&mut computer, &mut history, generation, 5);
+            }
+            assert_eq!(history.generations(), Some((0, 20)));
+
+            let mut expected_computer = create_computer();
+            let mut encoder = device.create_command_encoder(&Default::default());
+            expected_computer.enqueue_generations(&mut encoder, 23);
+            queue.submit(std::iter::once(encoder.finish()));
+
+            let mut encoder = device.create_command_encoder(&Default::default());
+            let snapshot_generation = history
+                .rewind(&device, &mut encoder, &computer, 30, 23)
+                .unwrap();
+            assert_eq!(snapshot_generation, 20);
+            computer.enqueue_generations(&mut encoder, 3);
+            queue.submit(std::iter::once(encoder.finish()));
+            let cells_at_23 = read_cells(&computer);
+            assert_eq!(cells_at_23, read_cells(&expected_computer));
+
+            // Editing after having rewound forgets the later snapshots:
+            history.before_edit(&device, &queue, &computer, 23);
+            computer
+                .write_cells(
+                    &device,
+                    &queue,
+                    Rect {
+                        x: 0,
+                        y: 0,
+                        width: 4,
+                        height: 4,
+                    },
+                    &[1; 16],
+                )
+                .unwrap();
+            assert_ne!(read_cells(&computer), cells_at_23);
+            advance(&mut computer, &mut history, 23, 5);
+            assert_eq!(history.generations(), Some((0, 23)));
+
+            let mut encoder = device.create_command_encoder(&Default::default());
+            assert_eq!(history.undo(&mut encoder, &computer), Some(23));
+            queue.submit(std::iter::once(encoder.finish()));
+            assert_eq!(read_cells(&computer), cells_at_23);
+            assert_eq!(history.generations(), Some((0, 20)));
+
+            // The oldest snapshots are dropped to stay within the memory budget:
+            let snapshot_size = computer.current_cells_buffer().size();
+            history.set_limits(10, 2 * snapshot_size);
+            assert_eq!(history.generations(), Some((10, 20)));
+        }
+    }
+}
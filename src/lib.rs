@@ -1,15 +1,45 @@
 #[cfg(target_os = "android")]
 mod android;
+mod camera;
+pub mod census;
+mod clipboard;
 pub mod computer;
+pub mod cpu;
+mod drawing;
 pub mod event_loop;
+mod graph;
+pub mod hashlife;
+mod history;
+pub mod oscillation;
 mod renderer;
+mod rle;
 pub mod rules;
+mod selection;
+pub mod session;
+pub mod simulation;
+#[cfg(test)]
+mod testing;
+pub mod topology;
+pub mod transform;
 #[cfg(target_family = "wasm")]
 mod web;
 
-use computer::{Computer, ComputerFactory};
+use camera::Camera;
+use census::Census;
+use computer::{CellStorage, Statistics};
+use drawing::Drawing;
+use graph::PopulationGraph;
+use history::History;
+use oscillation::{Oscillation, OscillationAction, OscillationDetector};
 use renderer::{Renderer, RendererFactory};
-use std::sync::Arc;
+pub use selection::Rect;
+use selection::Selection;
+use session::Session;
+use simulation::{Settings, Simulation};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use topology::Topology;
+use transform::{Transform, Transformer};
 use winit::window::Window;
 
 pub enum RenderResult {
@@ -18,36 +48,99 @@ pub enum RenderResult {
     Other,
 }
 
+/// The result of reading from the GPU, which is filled in once available.
+type PendingReadback<T> = Arc<Mutex<Option<T>>>;
+
+/// What to do with the bounding box of live cells once it has been read back.
+#[derive(Clone, Copy)]
+enum BoundingBoxUse {
+    Crop,
+    FitCamera,
+}
+
+/// An ongoing [`State::advance_to()`].
+struct FastForward {
+    target: u64,
+    /// The batches of generations submitted but not yet computed.
+    batches_in_flight: Arc<AtomicUsize>,
+}
+
 pub struct State {
-    pub(crate) cells_height: u32,
-    pub(crate) cells_width: u32,
-    computer: Computer,
-    computer_factory: ComputerFactory,
+    camera: Camera,
     config: wgpu::SurfaceConfiguration,
-    device: wgpu::Device,
+    cursor_position: Option<winit::dpi::PhysicalPosition<f64>>,
+    drawing: Option<Drawing>,
     elapsed_time: f32,
-    frame_count: u64,
+    fast_forward: Option<FastForward>,
+    /// Wakes up the event loop from GPU callbacks, to continue [`Self::advance_to()`].
+    wake_up: Option<Arc<dyn Fn() + Send + Sync>>,
+    pub(crate) generation_prompt: Option<String>,
+    history: History,
     pub(crate) generations_per_second: u8,
-    pub(crate) initial_density: u8,
+    /// Kept to create a new surface when resumed on Android.
+    #[cfg(target_os = "android")]
+    instance: wgpu::Instance,
     pub last_time: web_time::Instant,
+    pub(crate) modifiers: winit::keyboard::ModifiersState,
+    pub(crate) oscillation_action: OscillationAction,
+    oscillation_detector: OscillationDetector,
     pub(crate) paused: bool,
-    queue: wgpu::Queue,
+    panning: bool,
+    pending_bounding_box: Option<(BoundingBoxUse, PendingReadback<Option<Rect>>)>,
+    /// The generation that statistics were requested at, and the statistics.
+    pending_statistics: Option<(u64, PendingReadback<Statistics>)>,
+    population_graph: PopulationGraph,
     renderer: Renderer,
     renderer_factory: RendererFactory,
-    pub(crate) rule_idx: u32,
-    pub(crate) seed: u32,
+    selecting: bool,
+    pub(crate) selection: Option<Selection>,
+    pub(crate) show_population_graph: bool,
+    simulation: Simulation,
     pub(crate) size: winit::dpi::PhysicalSize<u32>,
-    surface: wgpu::Surface<'static>,
+    pub(crate) step_base: u32,
+    pub(crate) step_exponent: u32,
+    /// The latest statistics read back, which are requested every frame.
+    statistics: Statistics,
+    /// Dropped while suspended, as the window it was created for is then invalid.
+    surface: Option<wgpu::Surface<'static>>,
     texture_view_descriptor: wgpu::TextureViewDescriptor<'static>,
+    transformer: Transformer,
+    pub(crate) turbo: bool,
+    turbo_generations: u64,
     pub window: Arc<Window>,
 }
 impl State {
-    pub const ELIGIBLE_SIZES: [u32; 6] = [64, 128, 256, 512, 1024, 2048];
+    /// The frame duration that turbo mode adjusts the generations per frame to stay within.
+    const TURBO_FRAME_BUDGET: f32 = 1. / 30.;
+    /// The upper limit of generations per frame in turbo mode.
+    const MAX_TURBO_GENERATIONS: u64 = 10_000;
+    /// The default number of generations between snapshots used to rewind.
+    const HISTORY_CADENCE: u64 = 100;
+    /// The default maximum number of bytes used by snapshots used to rewind.
+    const HISTORY_MEMORY_BUDGET: u64 = 128 << 20;
+    /// The speeds that generations can be advanced at when not in turbo mode.
+    pub const GENERATIONS_PER_SECOND: std::ops::RangeInclusive<u8> = 1..=100;
+    /// The generations submitted at a time by [`Self::advance_to()`].
+    const FAST_FORWARD_BATCH: u64 = 1024;
+    /// The batches of [`Self::advance_to()`] kept submitted, so that the GPU does not wait
+    /// for the next one.
+    const FAST_FORWARD_BATCHES_IN_FLIGHT: usize = 3;
+
+    /// Grid widths stepped through when making the grid smaller or larger.
+    pub const ELIGIBLE_SIZES: [u32; 9] = [64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384];
 
+    /// Round a computed grid width or height to a whole number of cells.
+    fn rounded_size(size: f64) -> u32 {
+        (size.round() as u32).max(1)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         window: Window,
         rule_idx: Option<u32>,
-        grid_size: Option<u32>,
+        topology: Option<Topology>,
+        grid_size: Option<(u32, u32)>,
+        cell_storage: Option<CellStorage>,
         seed: Option<u32>,
         initial_density: Option<u8>,
         paused: bool,
@@ -59,26 +152,8 @@ impl State {
             .create_surface(Arc::clone(&window))
             .map_err(|_| "create_surface failed")?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .map_err(|e| format!("request_adapter failed: {e}"))?;
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: adapter.limits(),
-                label: None,
-                memory_hints: Default::default(),
-                trace: Default::default(),
-                experimental_features: Default::default(),
-            })
-            .await
-            .map_err(|e| format!("request_device failed: {e}"))?;
+        let (adapter, device, queue) =
+            simulation::request_device(&instance, Some(&surface), false).await?;
 
         let size = window.inner_size();
         let surface_caps = surface.get_capabilities(&adapter);
@@ -105,149 +180,385 @@ impl State {
             surface.configure(&device, &config);
         }
 
-        let cells_width = match grid_size {
-            Some(v) if Self::ELIGIBLE_SIZES.contains(&v) => v,
-            _ => 512,
-        };
-        let cells_height = cells_width;
-
         let rule_idx = match rule_idx {
             Some(idx) if idx < rules::RULES.len() as u32 => idx,
             _ => 0,
         };
-        let rule = &rules::RULES[rule_idx as usize];
-
-        let seed = seed.unwrap_or(0);
-
-        let initial_density = match initial_density {
-            Some(value) if value > 0 && value < 100 => value,
-            _ => 12,
+        let topology = topology.unwrap_or_default();
+        let cell_storage = cell_storage.unwrap_or_default();
+        let mut settings = Settings {
+            rule_idx,
+            topology,
+            cell_storage,
+            seed: seed.unwrap_or(0),
+            initial_density: match initial_density {
+                Some(value) if value > 0 && value < 100 => value,
+                _ => 12,
+            },
+            ..Settings::default()
         };
+        if let Some((cells_width, cells_height)) = grid_size {
+            let sized_settings = Settings {
+                cells_width,
+                cells_height,
+                ..settings
+            };
+            match sized_settings.validate(&device.limits()) {
+                Ok(()) => settings = sized_settings,
+                Err(e) => log::error!("{e}"),
+            }
+        }
+        if !settings
+            .topology
+            .supports_size(settings.cells_width, settings.cells_height)
+        {
+            settings.topology = Topology::default();
+        }
+        let (cells_width, cells_height) = (settings.cells_width, settings.cells_height);
 
         let generations_per_second = match generations_per_second {
             Some(value) if value > 0 && value < 100 => value,
             _ => 8,
         };
 
-        let computer_factory = ComputerFactory::new(&device);
-        let computer = computer_factory.create(
-            &device,
-            cells_width,
-            cells_height,
-            rule,
-            seed,
-            initial_density,
-            &queue,
-        );
+        let simulation = Simulation::new(device, queue, settings)?;
+        let device = simulation.device();
 
-        let renderer_factory = RendererFactory::new(&device);
+        let renderer_factory = RendererFactory::new(device);
         let renderer = renderer_factory.create(
-            &device,
-            &computer,
-            &computer_factory.size_buffer,
+            device,
+            simulation.computer(),
             cells_width,
             cells_height,
             surface_format,
         );
 
+        let population_graph = PopulationGraph::new(device, surface_format);
+
+        let transformer = Transformer::new(device);
+
         let last_time = web_time::Instant::now();
         let elapsed_time = 0.;
 
         let state = Self {
+            camera: Camera::new(cells_width, cells_height, size),
             generations_per_second,
-            initial_density,
             paused,
             last_time,
+            modifiers: winit::keyboard::ModifiersState::empty(),
+            oscillation_action: OscillationAction::default(),
+            oscillation_detector: OscillationDetector::default(),
+            panning: false,
+            pending_bounding_box: None,
+            pending_statistics: None,
+            population_graph,
             elapsed_time,
-            seed,
-            rule_idx,
+            fast_forward: None,
+            wake_up: None,
+            generation_prompt: None,
+            history: History::new(Self::HISTORY_CADENCE, Self::HISTORY_MEMORY_BUDGET),
+            selecting: false,
+            selection: None,
+            show_population_graph: false,
+            step_base: 10,
+            step_exponent: 0,
+            statistics: Statistics::default(),
             renderer_factory,
             renderer,
-            frame_count: 0,
-            device,
-            queue,
+            cursor_position: None,
+            drawing: None,
             texture_view_descriptor: wgpu::TextureViewDescriptor::default(),
+            transformer,
+            turbo: false,
+            turbo_generations: 1,
             window,
             config,
             size,
-            surface,
-            computer_factory,
-            computer,
-            cells_width,
-            cells_height,
+            surface: Some(surface),
+            #[cfg(target_os = "android")]
+            instance,
+            simulation,
         };
         state.inform_ui_about_state();
+        state.update_camera();
         Ok(state)
     }
 
+    /// Stop rendering while the window is invalid, which is when suspended on Android.
+    #[cfg(target_os = "android")]
+    pub(crate) fn suspend(&mut self) {
+        self.surface = None;
+    }
+
+    /// Continue rendering after [`Self::suspend()`] to a new window, keeping the simulation.
+    #[cfg(target_os = "android")]
+    pub(crate) fn resume(&mut self, window: Window) -> Result<(), String> {
+        let window = Arc::new(window);
+        let surface = self
+            .instance
+            .create_surface(Arc::clone(&window))
+            .map_err(|e| format!("create_surface failed: {e}"))?;
+        self.window = window;
+        self.surface = Some(surface);
+        self.last_time = web_time::Instant::now();
+        let size = self.window.inner_size();
+        if size.width > 0 && size.height > 0 {
+            self.resize(size);
+        }
+        Ok(())
+    }
+
     pub(crate) fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            if let Some(surface) = &self.surface {
+                surface.configure(self.simulation.device(), &self.config);
+            }
+            let settings = self.simulation.settings();
+            self.camera
+                .set_sizes(settings.cells_width, settings.cells_height, new_size);
+            self.update_camera();
         }
     }
 
+    /// The simulation being rendered, which is reset when changing its settings.
+    pub const fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
     pub fn set_initial_density(&mut self, new_density: u8) {
         if (1..=99).contains(&new_density) {
-            self.initial_density = new_density;
-            self.on_state_change();
+            self.change_settings(Settings {
+                initial_density: new_density,
+                ..self.simulation.settings()
+            });
         }
     }
 
     pub fn set_rule_idx(&mut self, new_rule_idx: u32) {
-        self.rule_idx = new_rule_idx;
-        let rule = &rules::RULES[self.rule_idx as usize];
-        self.initial_density = rule.initial_density;
-        self.on_state_change();
+        let Some(rule) = rules::RULES.get(new_rule_idx as usize) else {
+            log::error!("Invalid rule index: {new_rule_idx}");
+            return;
+        };
+        self.change_settings(Settings {
+            rule_idx: new_rule_idx,
+            initial_density: rule.initial_density,
+            ..self.simulation.settings()
+        });
+    }
+
+    /// Set the rule, topology and, if given, grid size from a rulestring such as `B3/S23:P100,60`.
+    ///
+    /// Only the neighbour counts of the built-in rules are supported.
+    pub fn set_rulestring(&mut self, rulestring: &str) -> Result<(), String> {
+        let parsed = rules::parse_rulestring(rulestring)?;
+        let rule_idx = rules::RULES
+            .iter()
+            .position(|rule| (rule.born, rule.survives) == (parsed.born, parsed.survives))
+            .ok_or_else(|| format!("Unsupported rule: '{rulestring}'"))?
+            as u32;
+        let settings = self.simulation.settings();
+        let (cells_width, cells_height) = parsed
+            .size
+            .unwrap_or((settings.cells_width, settings.cells_height));
+        self.try_change_settings(Settings {
+            rule_idx,
+            topology: parsed.topology,
+            cells_width,
+            cells_height,
+            initial_density: if rule_idx == settings.rule_idx {
+                settings.initial_density
+            } else {
+                rules::RULES[rule_idx as usize].initial_density
+            },
+            ..settings
+        })
+    }
+
+    pub fn set_topology(&mut self, new_topology: Topology) {
+        self.change_settings(Settings {
+            topology: new_topology,
+            ..self.simulation.settings()
+        });
+    }
+
+    /// Step to the next topology supported by the current grid size.
+    pub(crate) fn change_topology(&mut self) {
+        let settings = self.simulation.settings();
+        let new_topology = Topology::ALL
+            .into_iter()
+            .cycle()
+            .skip(settings.topology.index() as usize + 1)
+            .take(Topology::ALL.len())
+            .find(|topology| topology.supports_size(settings.cells_width, settings.cells_height))
+            .unwrap_or_default();
+        self.set_topology(new_topology);
+    }
+
+    /// Switch between storing cell ages and bit-packed cells, which allows larger grids.
+    pub fn set_cell_storage(&mut self, new_cell_storage: CellStorage) {
+        self.change_settings(Settings {
+            cell_storage: new_cell_storage,
+            ..self.simulation.settings()
+        });
+    }
+
+    pub(crate) fn toggle_cell_storage(&mut self) {
+        self.set_cell_storage(match self.simulation.settings().cell_storage {
+            CellStorage::Ages => CellStorage::Bits,
+            CellStorage::Bits => CellStorage::Ages,
+        });
     }
 
     pub(crate) fn change_rule(&mut self, next: bool) {
+        let rule_idx = self.simulation.settings().rule_idx;
         let new_rule_idx = if next {
-            (self.rule_idx + 1) % (rules::RULES.len() as u32)
-        } else if self.rule_idx == 0 {
+            (rule_idx + 1) % (rules::RULES.len() as u32)
+        } else if rule_idx == 0 {
             rules::RULES.len() as u32 - 1
         } else {
-            self.rule_idx - 1
+            rule_idx - 1
         };
         self.set_rule_idx(new_rule_idx);
     }
 
     fn inform_ui_about_state(&self) {
+        let settings = self.simulation.settings();
         #[cfg(not(target_family = "wasm"))]
         self.window.set_title(&format!(
-            "{} {}x{} 0.{} {} {}/s",
-            rules::RULES[self.rule_idx as usize].name(),
-            self.cells_width,
-            self.cells_height,
-            self.initial_density,
-            self.seed,
-            self.generations_per_second,
+            "{} {}x{}{} {} 0.{} {} {} population {} (+{} -{}){}{}{}",
+            settings.rule().name(),
+            settings.cells_width,
+            settings.cells_height,
+            match settings.cell_storage {
+                CellStorage::Ages => "",
+                CellStorage::Bits => " (bits)",
+            },
+            settings.topology.name(),
+            settings.initial_density,
+            settings.seed,
+            if self.turbo {
+                "turbo".to_string()
+            } else {
+                format!("{}/s", self.generations_per_second)
+            },
+            self.statistics.population,
+            self.statistics.births,
+            self.statistics.deaths,
+            self.oscillation()
+                .map(|oscillation| format!(" {}", Self::describe_oscillation(oscillation)))
+                .unwrap_or_default(),
+            if self.paused {
+                format!(
+                    " paused at {} (step {}^{})",
+                    self.simulation.generation(),
+                    self.step_base,
+                    self.step_exponent
+                )
+            } else {
+                String::new()
+            },
+            match self.oscillation_action {
+                OscillationAction::Report => String::new(),
+                action => format!(" ({} on repeat)", action.name()),
+            },
         ));
 
         #[cfg(target_family = "wasm")]
         web::set_new_state(
-            self.rule_idx,
-            self.cells_width,
-            self.seed,
-            self.initial_density,
+            settings.rule_idx,
+            &settings.rulestring(),
+            settings.cells_width,
+            settings.cells_height,
+            settings.topology.index(),
+            settings.cell_storage == CellStorage::Bits,
+            settings.seed,
+            settings.initial_density,
             self.paused,
             self.generations_per_second,
-            if self.paused { self.frame_count } else { 0 },
+            self.turbo,
+            self.step_base,
+            self.step_exponent,
+            if self.paused {
+                self.simulation.generation()
+            } else {
+                0
+            },
+            self.statistics.population,
+            self.statistics.births,
+            self.statistics.deaths,
         );
+        #[cfg(target_family = "wasm")]
+        {
+            let generation = self.simulation.generation();
+            let (oldest, latest) = self
+                .history
+                .generations()
+                .unwrap_or((generation, generation));
+            web::set_timeline(oldest, latest.max(generation), generation);
+        }
+        #[cfg(target_family = "wasm")]
+        {
+            let oscillation = self.oscillation();
+            web::set_oscillation(
+                self.oscillation_action.index(),
+                oscillation.map_or(0, |oscillation| oscillation.period),
+                oscillation.map_or(0, |oscillation| oscillation.generation),
+            );
+        }
+    }
+
+    pub(crate) fn reset_with_size(&mut self, new_cells_width: u32, new_cells_height: u32) {
+        self.change_settings(Settings {
+            cells_width: new_cells_width,
+            cells_height: new_cells_height,
+            ..self.simulation.settings()
+        });
+    }
+
+    /// Step the grid width through `ELIGIBLE_SIZES`, keeping the aspect ratio.
+    pub(crate) fn change_grid_size(&mut self, larger: bool) {
+        let Settings {
+            cells_width,
+            cells_height,
+            ..
+        } = self.simulation.settings();
+        let new_width = if larger {
+            Self::ELIGIBLE_SIZES
+                .into_iter()
+                .find(|&size| size > cells_width)
+        } else {
+            Self::ELIGIBLE_SIZES
+                .into_iter()
+                .rev()
+                .find(|&size| size < cells_width)
+        };
+        if let Some(new_width) = new_width {
+            let new_height = Self::rounded_size(
+                f64::from(new_width) * f64::from(cells_height) / f64::from(cells_width),
+            );
+            self.reset_with_size(new_width, new_height);
+        }
     }
 
-    pub(crate) fn reset_with_cells_width(&mut self, new_cells_width: u32, new_cells_height: u32) {
-        self.cells_width = new_cells_width;
-        self.cells_height = new_cells_height;
-        self.on_state_change();
+    /// Change the grid height so that the grid has the same aspect ratio as the window.
+    pub(crate) fn fit_grid_to_window(&mut self) {
+        let cells_width = self.simulation.settings().cells_width;
+        let new_height = Self::rounded_size(
+            f64::from(cells_width) * f64::from(self.size.height)
+                / f64::from(self.size.width.max(1)),
+        );
+        self.reset_with_size(cells_width, new_height);
     }
 
     pub(crate) fn reset(&mut self) {
         use rand::Rng;
-        self.seed = rand::rng().next_u32();
-        self.on_state_change();
+        self.change_settings(Settings {
+            seed: rand::rng().next_u32(),
+            ..self.simulation.settings()
+        });
     }
 
     pub(crate) fn toggle_pause(&mut self) {
@@ -255,76 +566,965 @@ impl State {
         self.inform_ui_about_state();
     }
 
-    pub(crate) fn set_generations_per_second(&mut self, new_value: u8) {
-        if new_value > 0 && new_value <= 100 {
-            self.generations_per_second = new_value;
+    /// The generations advanced by [`Self::step()`], which is `step_base^step_exponent`
+    /// like the step size of Golly.
+    fn step_size(&self) -> u64 {
+        u64::from(self.step_base).saturating_pow(self.step_exponent)
+    }
+
+    /// Set the step size to `base^exponent`, where the base is 2 or 10.
+    pub fn set_step_size(&mut self, base: u32, exponent: u32) {
+        if !matches!(base, 2 | 10) || u64::from(base).checked_pow(exponent).is_none() {
+            log::error!("Invalid step size {base}^{exponent}");
+            return;
+        }
+        self.step_base = base;
+        self.step_exponent = exponent;
+        self.inform_ui_about_state();
+    }
+
+    pub(crate) fn change_step_exponent(&mut self, larger: bool) {
+        if larger {
+            self.set_step_size(self.step_base, self.step_exponent + 1);
+        } else if self.step_exponent > 0 {
+            self.set_step_size(self.step_base, self.step_exponent - 1);
+        }
+    }
+
+    pub(crate) fn toggle_step_base(&mut self) {
+        self.set_step_size(if self.step_base == 10 { 2 } else { 10 }, 0);
+    }
+
+    /// Pause and advance the step size number of generations.
+    pub fn step(&mut self) {
+        if self.fast_forward.is_some() {
+            return;
+        }
+        self.paused = true;
+        let step_size = self.step_size();
+        if step_size > Self::FAST_FORWARD_BATCH {
+            self.advance_to(self.simulation.generation() + step_size);
+            return;
+        }
+        let mut encoder =
+            self.simulation
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("step_encoder"),
+                });
+        self.enqueue_generations(&mut encoder, step_size);
+        self.simulation
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+        self.inform_ui_about_state();
+    }
+
+    /// Pause and go back the step size number of generations, or as far as possible.
+    pub fn step_back(&mut self) {
+        let generation = self.simulation.generation();
+        let oldest_generation = self
+            .history
+            .generations()
+            .map_or(generation, |(oldest, _)| oldest);
+        self.rewind_to(
+            generation
+                .saturating_sub(self.step_size())
+                .max(oldest_generation),
+        );
+    }
+
+    /// Pause at a previous generation, restored from the snapshot before it and computed
+    /// forward from there. Later generations that have been reached can be returned to
+    /// until advancing or editing from the restored generation.
+    pub fn rewind_to(&mut self, generation: u64) {
+        self.cancel_advance();
+        self.paused = true;
+        if generation == self.simulation.generation() {
             self.inform_ui_about_state();
+            return;
         }
+        let mut encoder =
+            self.simulation
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("rewind_encoder"),
+                });
+        let Some(snapshot_generation) = self.history.rewind(
+            self.simulation.device(),
+            &mut encoder,
+            self.simulation.computer(),
+            self.simulation.generation(),
+            generation,
+        ) else {
+            log::error!("No history at generation {generation}");
+            return;
+        };
+        self.simulation
+            .computer_mut()
+            .enqueue_generations(&mut encoder, generation - snapshot_generation);
+        self.simulation
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+        self.simulation.set_generation(generation);
+        self.inform_ui_about_state();
     }
 
-    fn on_state_change(&mut self) {
+    /// Restore the cells from before the latest edits.
+    pub fn undo(&mut self) {
+        let mut encoder =
+            self.simulation
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("undo_encoder"),
+                });
+        let Some(generation) = self.history.undo(&mut encoder, self.simulation.computer()) else {
+            log::error!("Nothing to undo");
+            return;
+        };
+        self.simulation
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+        self.cancel_advance();
+        self.simulation.set_generation(generation);
         self.inform_ui_about_state();
+    }
 
-        self.frame_count = 0;
+    /// Set how many generations there are between snapshots used to rewind, and the
+    /// maximum number of bytes used by them.
+    pub fn set_history_limits(&mut self, cadence: u64, memory_budget: u64) {
+        self.history.set_limits(cadence, memory_budget);
+        self.inform_ui_about_state();
+    }
 
-        let rule = &rules::RULES[self.rule_idx as usize];
+    /// Advance generations, taking snapshots to rewind to as needed.
+    fn enqueue_generations(&mut self, encoder: &mut wgpu::CommandEncoder, generations: u64) {
+        if generations == 0 {
+            return;
+        }
+        self.history.before_advance(
+            self.simulation.device(),
+            encoder,
+            self.simulation.computer(),
+            self.simulation.generation(),
+        );
+        self.simulation.enqueue_generations(encoder, generations);
+    }
 
-        self.computer = self.computer_factory.create(
-            &self.device,
-            self.cells_width,
-            self.cells_height,
-            rule,
-            self.seed,
-            self.initial_density,
-            &self.queue,
+    /// To be called before editing cells, so that the edits can be undone.
+    fn before_edit(&mut self) {
+        self.history.before_edit(
+            self.simulation.device(),
+            self.simulation.queue(),
+            self.simulation.computer(),
+            self.simulation.generation(),
         );
+        self.oscillation_detector.clear();
+        // Statistics of the cells before the edit would be taken as repeating:
+        self.pending_statistics = None;
+    }
 
-        self.renderer = self.renderer_factory.create(
-            &self.device,
-            &self.computer,
-            &self.computer_factory.size_buffer,
-            self.cells_width,
-            self.cells_height,
-            self.config.format,
+    /// The number of generations computed since the grid was last reset.
+    pub const fn generation(&self) -> u64 {
+        self.simulation.generation()
+    }
+
+    /// Compute generations as fast as the GPU allows until reaching `generation`, and pause
+    /// there. The generations are submitted in batches independently of rendering and of
+    /// `generations_per_second`, with the progress shown in the UI.
+    pub fn advance_to(&mut self, generation: u64) {
+        if generation <= self.simulation.generation() {
+            log::error!(
+                "Cannot advance to generation {generation} from generation {}",
+                self.simulation.generation()
+            );
+            return;
+        }
+        self.fast_forward = Some(FastForward {
+            target: generation,
+            batches_in_flight: Arc::new(AtomicUsize::new(0)),
+        });
+        self.continue_advance();
+    }
+
+    /// Stop an ongoing [`Self::advance_to()`], pausing at the current generation.
+    pub fn cancel_advance(&mut self) {
+        if self.stop_advance() {
+            self.paused = true;
+            self.inform_ui_about_state();
+        }
+    }
+
+    /// Clear an ongoing [`Self::advance_to()`] and hide its progress, returning whether
+    /// there was one.
+    fn stop_advance(&mut self) -> bool {
+        let was_advancing = self.fast_forward.take().is_some();
+        if was_advancing {
+            self.inform_ui_about_progress();
+        }
+        was_advancing
+    }
+
+    pub(crate) const fn is_advancing(&self) -> bool {
+        self.fast_forward.is_some()
+    }
+
+    /// Keep batches of generations of an ongoing [`Self::advance_to()`] submitted, called
+    /// again from the event loop each time a batch has been computed, independently of
+    /// rendering.
+    pub(crate) fn continue_advance(&mut self) {
+        let Some(fast_forward) = &self.fast_forward else {
+            return;
+        };
+        let (target, batches_in_flight) = (
+            fast_forward.target,
+            Arc::clone(&fast_forward.batches_in_flight),
         );
+        if self.simulation.generation() >= target {
+            if batches_in_flight.load(Ordering::Acquire) == 0 {
+                self.stop_advance();
+                self.paused = true;
+                self.inform_ui_about_state();
+            }
+            return;
+        }
+
+        while self.simulation.generation() < target
+            && batches_in_flight.load(Ordering::Acquire) < Self::FAST_FORWARD_BATCHES_IN_FLIGHT
+        {
+            let generations = (target - self.simulation.generation()).min(Self::FAST_FORWARD_BATCH);
+            let mut encoder =
+                self.simulation
+                    .device()
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("fast_forward_encoder"),
+                    });
+            self.enqueue_generations(&mut encoder, generations);
+            self.simulation
+                .queue()
+                .submit(std::iter::once(encoder.finish()));
+            batches_in_flight.fetch_add(1, Ordering::AcqRel);
+            let batches_in_flight = Arc::clone(&batches_in_flight);
+            let wake_up = self.wake_up.clone();
+            self.simulation.queue().on_submitted_work_done(move || {
+                batches_in_flight.fetch_sub(1, Ordering::AcqRel);
+                if let Some(wake_up) = wake_up {
+                    wake_up();
+                }
+            });
+        }
+
+        // Callbacks are otherwise only called on native platforms when later work polls
+        // the device, which may not happen while the window is hidden:
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let device = self.simulation.device().clone();
+            std::thread::spawn(move || {
+                if let Err(e) = device.poll(wgpu::PollType::wait_indefinitely()) {
+                    log::error!("Failed waiting for generations: {e}");
+                }
+            });
+        }
+        self.inform_ui_about_progress();
     }
 
-    pub(crate) fn render(&mut self) -> RenderResult {
-        if self.window.inner_size().height < 10 {
-            return RenderResult::Ok;
+    /// Set how to wake up the event loop to call [`Self::continue_advance()`], such as
+    /// by sending it an event.
+    pub fn set_wake_up(&mut self, wake_up: impl Fn() + Send + Sync + 'static) {
+        self.wake_up = Some(Arc::new(wake_up));
+    }
+
+    /// Ask for a generation to advance to, typed on desktop and prompted for on the web.
+    pub(crate) fn start_generation_prompt(&mut self) {
+        #[cfg(target_family = "wasm")]
+        web::prompt_generation();
+        #[cfg(not(target_family = "wasm"))]
+        {
+            self.generation_prompt = Some(String::new());
+            self.inform_ui_about_progress();
+        }
+    }
+
+    /// Handle a key pressed while [`Self::start_generation_prompt()`] is active.
+    pub(crate) fn generation_prompt_input(&mut self, key: &winit::keyboard::Key) {
+        use winit::keyboard::{Key, NamedKey};
+
+        let Some(prompt) = &mut self.generation_prompt else {
+            return;
+        };
+        match key {
+            Key::Character(c) if c.chars().all(|c| c.is_ascii_digit()) => prompt.push_str(c),
+            Key::Named(NamedKey::Backspace) => {
+                prompt.pop();
+            }
+            Key::Named(NamedKey::Enter) => {
+                let generation = prompt.parse::<u64>();
+                self.generation_prompt = None;
+                self.inform_ui_about_state();
+                match generation {
+                    Ok(generation) => self.advance_to(generation),
+                    Err(e) => log::error!("Invalid generation: {e}"),
+                }
+                return;
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.generation_prompt = None;
+                self.inform_ui_about_state();
+                return;
+            }
+            _ => {}
+        }
+        self.inform_ui_about_progress();
+    }
+
+    /// Show the generation prompt or the progress of [`Self::advance_to()`].
+    fn inform_ui_about_progress(&self) {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(prompt) = &self.generation_prompt {
+            self.window
+                .set_title(&format!("Go to generation (Enter/Escape): {prompt}"));
+        } else if let Some(fast_forward) = &self.fast_forward {
+            self.window.set_title(&format!(
+                "Generation {}/{} (Escape to cancel)",
+                self.simulation.generation(),
+                fast_forward.target
+            ));
         }
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("command_encoder_descriptor"),
+        #[cfg(target_family = "wasm")]
+        web::set_progress(
+            self.simulation.generation(),
+            self.fast_forward
+                .as_ref()
+                .map_or(0, |fast_forward| fast_forward.target),
+        );
+    }
+
+    /// Run as many generations each frame as the GPU keeps up with, instead of
+    /// `generations_per_second`.
+    pub fn set_turbo(&mut self, turbo: bool) {
+        self.turbo = turbo;
+        self.turbo_generations = 1;
+        self.inform_ui_about_state();
+    }
+
+    /// Step through what to do once the grid is static or periodic.
+    pub(crate) fn change_oscillation_action(&mut self) {
+        self.set_oscillation_action(self.oscillation_action.next());
+    }
+
+    pub fn set_oscillation_action(&mut self, action: OscillationAction) {
+        self.oscillation_action = action;
+        self.inform_ui_about_state();
+    }
+
+    /// Count the objects in the grid, which is logged and shown in the web version once
+    /// the cells have been read back.
+    ///
+    /// Counting takes a while in large grids, so it runs on a worker thread on native
+    /// platforms, and in a task resolving with the readback on the web.
+    pub(crate) fn take_census(&self) {
+        let settings = self.simulation.settings();
+        let period = self
+            .oscillation()
+            .map_or(1, |oscillation| oscillation.period);
+        let generation = self.simulation.generation();
+        let cells = self.simulation.read_cells();
+        spawn(async move {
+            let cells = match cells.await {
+                Ok(cells) => cells,
+                Err(e) => {
+                    log::error!("{e}");
+                    return;
+                }
+            };
+            let take_census = move || {
+                let census = Census::take(
+                    settings.rule(),
+                    settings.topology,
+                    settings.cells_width,
+                    settings.cells_height,
+                    period,
+                    |x, y| cells.get(x, y) > 0,
+                );
+                log::info!("Census at generation {generation}:{census}");
+                #[cfg(target_family = "wasm")]
+                web::show_census(generation, &census.to_json());
+            };
+            #[cfg(target_family = "wasm")]
+            take_census();
+            #[cfg(not(target_family = "wasm"))]
+            std::thread::spawn(take_census);
+        });
+    }
+
+    /// The repetition of the grid detected since it was last reset or edited.
+    pub const fn oscillation(&self) -> Option<Oscillation> {
+        self.oscillation_detector.oscillation()
+    }
+
+    fn describe_oscillation(oscillation: Oscillation) -> String {
+        if oscillation.period == 1 {
+            format!("static from generation {}", oscillation.generation)
+        } else {
+            format!(
+                "period {} from generation {}",
+                oscillation.period, oscillation.generation
+            )
+        }
+    }
+
+    /// Switch between a linear and logarithmic scale of the population graph.
+    pub(crate) fn toggle_population_graph_log_scale(&mut self) {
+        self.population_graph.log_scale = !self.population_graph.log_scale;
+        self.show_population_graph = true;
+    }
+
+    pub(crate) fn set_generations_per_second(&mut self, new_value: u8) {
+        if Self::GENERATIONS_PER_SECOND.contains(&new_value) {
+            self.generations_per_second = new_value;
+            self.inform_ui_about_state();
+        }
+    }
+
+    pub(crate) fn set_cursor_position(
+        &mut self,
+        position: Option<winit::dpi::PhysicalPosition<f64>>,
+    ) {
+        let previous_position = std::mem::replace(&mut self.cursor_position, position);
+        if self.panning
+            && let (Some(from), Some(to)) = (previous_position, position)
+        {
+            self.camera.drag(from, to);
+            self.update_camera();
+        }
+        self.draw_at_cursor();
+        if self.selecting
+            && let (Some(mut selection), Some(cell)) = (self.selection, self.cell_at_cursor())
+        {
+            selection.corner = cell;
+            self.set_selection(Some(selection));
+        }
+    }
+
+    fn cell_at_cursor(&self) -> Option<(u32, u32)> {
+        self.cursor_position
+            .and_then(|position| self.camera.cell_at(position))
+    }
+
+    /// Start a stroke at the cursor which either draws (`alive`) or erases cells.
+    pub(crate) fn start_drawing(&mut self, alive: bool) {
+        self.drawing = Some(Drawing {
+            alive,
+            last_cell: None,
+        });
+        self.draw_at_cursor();
+    }
+
+    /// Start selecting a rectangle spanned from the cell at the cursor.
+    pub(crate) fn start_selecting(&mut self) {
+        let selection = self.cell_at_cursor().map(|cell| Selection {
+            anchor: cell,
+            corner: cell,
+        });
+        self.selecting = selection.is_some();
+        self.set_selection(selection);
+    }
+
+    /// Start dragging the grid around with the cursor.
+    pub(crate) fn start_panning(&mut self) {
+        self.panning = true;
+    }
+
+    pub(crate) fn release_pointer(&mut self) {
+        self.drawing = None;
+        self.panning = false;
+        self.selecting = false;
+    }
+
+    fn update_camera(&self) {
+        self.simulation.queue().write_buffer(
+            &self.renderer_factory.camera_buffer,
+            0,
+            bytemuck::cast_slice(&self.camera.uniform_array()),
+        );
+    }
+
+    /// Zoom in (`factor` above 1) or out around the cursor, or the center of the window.
+    pub(crate) fn zoom(&mut self, factor: f32, at_cursor: bool) {
+        let position = self
+            .cursor_position
+            .filter(|_| at_cursor)
+            .unwrap_or_else(|| {
+                winit::dpi::PhysicalPosition::new(
+                    f64::from(self.size.width) / 2.,
+                    f64::from(self.size.height) / 2.,
+                )
             });
+        self.camera.zoom_around(factor, position);
+        self.update_camera();
+    }
 
-        let frequency = 1.0 / f32::from(self.generations_per_second);
-        loop {
-            let advance_state = if self.paused {
-                false
-            } else {
-                self.elapsed_time += self.last_time.elapsed().as_secs_f32();
-                self.elapsed_time > frequency
+    /// Move the camera by a fraction of the visible part of the grid.
+    pub(crate) fn pan(&mut self, dx: f32, dy: f32) {
+        self.camera.pan(dx, dy);
+        self.update_camera();
+    }
+
+    /// Show the whole grid.
+    pub(crate) fn reset_camera(&mut self) {
+        let settings = self.simulation.settings();
+        self.camera = Camera::new(settings.cells_width, settings.cells_height, self.size);
+        self.update_camera();
+    }
+
+    pub(crate) fn fit_camera_to_live_cells(&mut self) {
+        self.request_bounding_box(BoundingBoxUse::FitCamera);
+    }
+
+    fn draw_at_cursor(&mut self) {
+        let Some(mut drawing) = self.drawing else {
+            return;
+        };
+        let cell = self.cell_at_cursor();
+        if let Some(cell) = cell {
+            let cells = drawing::line(drawing.last_cell.unwrap_or(cell), cell);
+            self.before_edit();
+            if let Err(e) = self.simulation.set_cells(&cells, u32::from(drawing.alive)) {
+                log::error!("{e}");
+            }
+        }
+        drawing.last_cell = cell;
+        self.drawing = Some(drawing);
+    }
+
+    pub(crate) fn set_selection(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+        if let Some(selection) = selection {
+            let rect = selection.rect();
+            self.simulation.queue().write_buffer(
+                &self.renderer_factory.selection_buffer,
+                0,
+                bytemuck::cast_slice(&[rect.x, rect.y, rect.width, rect.height]),
+            );
+        }
+    }
+
+    pub(crate) fn select_all(&mut self) {
+        let settings = self.simulation.settings();
+        self.set_selection(Some(Selection {
+            anchor: (0, 0),
+            corner: (settings.cells_width - 1, settings.cells_height - 1),
+        }));
+    }
+
+    /// Copy the selected cells to the clipboard in RLE format.
+    pub(crate) fn copy_selection(&self) {
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let rect = selection.rect();
+        let rulestring = self.simulation.settings().rulestring();
+        let cells = self.simulation.read_cells();
+        spawn(async move {
+            let cells = match cells.await {
+                Ok(cells) => cells,
+                Err(e) => {
+                    log::error!("{e}");
+                    return;
+                }
             };
-            self.last_time = web_time::Instant::now();
+            // RLE has the top row first, while the grid has y = 0 at the bottom.
+            let rle = rle::encode(rect.width, rect.height, &rulestring, |x, y| {
+                let cell_x = rect.x + x;
+                let cell_y = rect.y + rect.height - 1 - y;
+                cells.get(cell_x, cell_y) > 0
+            });
+            clipboard::set_text(&rle);
+        });
+    }
+
+    pub(crate) fn cut_selection(&mut self) {
+        self.copy_selection();
+        self.clear_selection();
+    }
+
+    pub(crate) fn clear_selection(&mut self) {
+        if let Some(selection) = self.selection {
+            self.fill_rect(selection.rect(), || 0);
+        }
+    }
+
+    pub(crate) fn clear_outside_selection(&mut self) {
+        if let Some(selection) = self.selection {
+            let settings = self.simulation.settings();
+            for rect in selection.outside(settings.cells_width, settings.cells_height) {
+                self.fill_rect(rect, || 0);
+            }
+        }
+    }
+
+    pub(crate) fn fill_selection(&mut self) {
+        if let Some(selection) = self.selection {
+            self.fill_rect(selection.rect(), || 1);
+        }
+    }
+
+    /// Randomize the selected cells using the current initial density.
+    pub(crate) fn randomize_selection(&mut self) {
+        use rand::RngExt as _;
+
+        if let Some(selection) = self.selection {
+            let mut rng = rand::rng();
+            let density = f32::from(self.simulation.settings().initial_density) * 0.01;
+            self.fill_rect(selection.rect(), || {
+                u32::from(rng.random::<f32>() < density)
+            });
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, mut cell_value: impl FnMut() -> u32) {
+        self.before_edit();
+        let values = (0..rect.width as usize * rect.height as usize)
+            .map(|_| cell_value())
+            .collect::<Vec<_>>();
+        if let Err(e) = self.simulation.write_cells(rect, &values) {
+            log::error!("{e}");
+        }
+    }
+
+    pub(crate) fn apply_transform(&mut self, transform: &Transform) {
+        let settings = self.simulation.settings();
+        let (new_width, new_height) =
+            transform.destination_size(settings.cells_width, settings.cells_height);
+        if (new_width, new_height) == (settings.cells_width, settings.cells_height) {
+            self.before_edit();
+            let device = self.simulation.device().clone();
+            let queue = self.simulation.queue().clone();
+            if let Err(e) = self.simulation.computer_mut().transform(
+                &self.transformer,
+                &device,
+                &queue,
+                transform,
+            ) {
+                log::error!("{e}");
+            }
+        } else {
+            self.replace_grid(new_width, new_height, transform);
+        }
+    }
+
+    /// Shrink the grid to the smallest size containing all live cells, which are kept centered.
+    pub(crate) fn crop_to_live_cells(&mut self) {
+        self.request_bounding_box(BoundingBoxUse::Crop);
+    }
+
+    /// Compute the bounding box of live cells on the GPU, to be used once it has been
+    /// read back when rendering the next frame.
+    fn request_bounding_box(&mut self, bounding_box_use: BoundingBoxUse) {
+        let pending_bounding_box = Arc::new(Mutex::new(None));
+        let pending_bounding_box_in_callback = Arc::clone(&pending_bounding_box);
+        let settings = self.simulation.settings();
+        self.transformer.read_bounding_box(
+            self.simulation.device(),
+            self.simulation.queue(),
+            settings.cell_storage,
+            self.simulation.computer().current_cells_buffer(),
+            (settings.cells_width, settings.cells_height),
+            move |bounding_box| {
+                if let Ok(mut pending_bounding_box) = pending_bounding_box_in_callback.lock() {
+                    *pending_bounding_box = Some(bounding_box);
+                }
+            },
+        );
+        self.pending_bounding_box = Some((bounding_box_use, pending_bounding_box));
+    }
 
-            if advance_state {
-                self.elapsed_time -= frequency;
-                self.frame_count += 1;
+    fn request_statistics(&mut self) {
+        let pending_statistics = Arc::new(Mutex::new(None));
+        let pending_statistics_in_callback = Arc::clone(&pending_statistics);
+        self.simulation.statistics(move |statistics| {
+            if let Ok(mut pending_statistics) = pending_statistics_in_callback.lock() {
+                *pending_statistics = Some(statistics);
             }
+        });
+        self.pending_statistics = Some((self.simulation.generation(), pending_statistics));
+    }
 
-            if advance_state {
-                self.computer.enqueue(&mut encoder);
+    fn apply_pending_statistics(&mut self) {
+        let Some((generation, statistics)) =
+            self.pending_statistics
+                .as_ref()
+                .and_then(|(generation, pending_statistics)| {
+                    Some((*generation, pending_statistics.lock().ok()?.take()?))
+                })
+        else {
+            return;
+        };
+        self.pending_statistics = None;
+        self.population_graph
+            .record(generation, statistics.population);
+        let was_oscillating = self.oscillation_detector.oscillation().is_some();
+        let oscillation = self.oscillation_detector.record(generation, statistics);
+        if let Some(oscillation) = oscillation {
+            log::info!("{}", Self::describe_oscillation(oscillation));
+            if !was_oscillating {
+                self.take_census();
+                match self.oscillation_action {
+                    OscillationAction::Report => {}
+                    OscillationAction::Pause => {
+                        self.stop_advance();
+                        self.paused = true;
+                    }
+                    OscillationAction::Reset => {
+                        self.reset();
+                        return;
+                    }
+                }
             }
-            if !advance_state {
-                break;
+        }
+        // The hash changes every generation, but is not shown:
+        let shown_statistics_unchanged = Statistics {
+            hash: statistics.hash,
+            ..self.statistics
+        } == statistics;
+        if oscillation.is_none() && shown_statistics_unchanged {
+            return;
+        }
+        self.statistics = statistics;
+        // Not replacing the progress shown while advancing or prompting:
+        if self.fast_forward.is_some() || self.generation_prompt.is_some() {
+            self.inform_ui_about_progress();
+        } else {
+            self.inform_ui_about_state();
+        }
+    }
+
+    fn apply_pending_bounding_box(&mut self) {
+        let Some((bounding_box_use, bounding_box)) = self.pending_bounding_box.as_ref().and_then(
+            |(bounding_box_use, pending_bounding_box)| {
+                Some((*bounding_box_use, pending_bounding_box.lock().ok()?.take()?))
+            },
+        ) else {
+            return;
+        };
+        self.pending_bounding_box = None;
+        let Some(bounding_box) = bounding_box else {
+            return;
+        };
+        match bounding_box_use {
+            BoundingBoxUse::Crop => self.crop(bounding_box),
+            BoundingBoxUse::FitCamera => {
+                self.camera.fit(bounding_box);
+                self.update_camera();
             }
         }
+    }
+
+    fn crop(&mut self, bounding_box: Rect) {
+        let settings = self.simulation.settings();
+        let (new_width, new_height) = if settings.topology == Topology::Sphere {
+            let side = bounding_box.width.max(bounding_box.height);
+            (side, side)
+        } else {
+            (bounding_box.width, bounding_box.height)
+        };
+        if (new_width, new_height) == (settings.cells_width, settings.cells_height) {
+            return;
+        }
+        self.replace_grid(
+            new_width,
+            new_height,
+            &Transform::translate(-(bounding_box.x as i32), -(bounding_box.y as i32)),
+        );
+    }
+
+    /// Replace the grid with one of a new size, filled by transforming the current grid.
+    fn replace_grid(&mut self, new_width: u32, new_height: u32, transform: &Transform) {
+        let settings = self.simulation.settings();
+        let new_settings = Settings {
+            cells_width: new_width,
+            cells_height: new_height,
+            ..settings
+        };
+        let new_computer = match self.simulation.create_computer(new_settings, 0) {
+            Ok(computer) => computer,
+            Err(e) => {
+                log::error!("{e}");
+                return;
+            }
+        };
+
+        let mut encoder =
+            self.simulation
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("replace_grid_encoder"),
+                });
+        if let Err(e) = self.transformer.enqueue(
+            self.simulation.device(),
+            &mut encoder,
+            transform,
+            settings.cell_storage,
+            self.simulation.computer().current_cells_buffer(),
+            (settings.cells_width, settings.cells_height),
+            new_computer.current_cells_buffer(),
+            (new_width, new_height),
+        ) {
+            log::error!("{e}");
+            return;
+        }
+        self.simulation
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+
+        let generation = self.simulation.generation();
+        self.simulation
+            .replace_computer(new_settings, new_computer, generation);
+        self.on_computer_change();
+        self.set_selection(None);
+        self.reset_camera();
+        self.inform_ui_about_state();
+    }
+
+    /// Start rendering a new computer of the simulation, such as after replacing the grid.
+    fn on_computer_change(&mut self) {
+        self.history.clear();
+        self.population_graph.clear();
+        self.oscillation_detector.clear();
+        // Statistics of the previous computer would end up in the graph:
+        self.pending_statistics = None;
+        self.drawing = None;
+        let settings = self.simulation.settings();
+        self.renderer = self.renderer_factory.create(
+            self.simulation.device(),
+            self.simulation.computer(),
+            settings.cells_width,
+            settings.cells_height,
+            self.config.format,
+        );
+    }
+
+    /// Capture the settings and cells of the simulation, which are passed to
+    /// `on_saved` once the cells have been read back from the GPU.
+    pub fn save_session(&self, on_saved: impl FnOnce(Vec<u8>) + 'static) {
+        let settings = self.simulation.settings();
+        let mut session = Session {
+            rulestring: settings.rule().rulestring(),
+            topology: settings.topology,
+            cells_width: settings.cells_width,
+            cells_height: settings.cells_height,
+            cell_storage: settings.cell_storage,
+            generation: self.simulation.generation(),
+            seed: settings.seed,
+            initial_density: settings.initial_density,
+            generations_per_second: self.generations_per_second,
+            words: Vec::new(),
+        };
+        let cells = self.simulation.read_cells();
+        spawn(async move {
+            match cells.await.and_then(|cells| {
+                session.words = cells.into_words();
+                session.to_bytes()
+            }) {
+                Ok(bytes) => on_saved(bytes),
+                Err(e) => log::error!("{e}"),
+            }
+        });
+    }
 
-        let current = self.surface.get_current_texture();
-        let output = match current {
+    /// Restore a simulation saved by [`Self::save_session()`].
+    pub fn load_session(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let session = Session::from_bytes(bytes)?;
+        let rule_idx = rules::RULES
+            .iter()
+            .position(|rule| rule.rulestring() == session.rulestring)
+            .ok_or_else(|| format!("Unsupported rule: '{}'", session.rulestring))?
+            as u32;
+        self.simulation.restore(
+            Settings {
+                rule_idx,
+                topology: session.topology,
+                cells_width: session.cells_width,
+                cells_height: session.cells_height,
+                cell_storage: session.cell_storage,
+                seed: session.seed,
+                initial_density: session.initial_density,
+            },
+            session.generation,
+            &session.words,
+        )?;
+
+        self.cancel_advance();
+        self.generations_per_second = session.generations_per_second;
+        self.selecting = false;
+        self.on_computer_change();
+        self.set_selection(None);
+        self.reset_camera();
+        self.inform_ui_about_state();
+        Ok(())
+    }
+
+    /// Save the session to the storage of the platform.
+    pub(crate) fn store_session(&self) {
+        self.save_session(|bytes| session::store(&bytes));
+    }
+
+    /// Load the session from the storage of the platform, which on the web is
+    /// instead requested using `web::request_stored_session()`.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn restore_session(&mut self) {
+        if let Err(e) = session::stored().and_then(|bytes| self.load_session(&bytes)) {
+            log::error!("{e}");
+        }
+    }
+
+    /// Start over with new settings, or keep the current ones if they are not supported.
+    fn change_settings(&mut self, settings: Settings) {
+        if let Err(e) = self.try_change_settings(settings) {
+            log::error!("{e}");
+        }
+    }
+
+    fn try_change_settings(&mut self, settings: Settings) -> Result<(), String> {
+        let previous_settings = self.simulation.settings();
+        if let Err(e) = self.simulation.reset(settings) {
+            // Let the UI show the settings that are still in use:
+            self.inform_ui_about_state();
+            return Err(e);
+        }
+        self.stop_advance();
+        self.selecting = false;
+        self.selection = None;
+        self.on_computer_change();
+        if (settings.cells_width, settings.cells_height)
+            != (
+                previous_settings.cells_width,
+                previous_settings.cells_height,
+            )
+        {
+            self.reset_camera();
+        }
+        self.inform_ui_about_state();
+        Ok(())
+    }
+
+    pub(crate) fn render(&mut self) -> RenderResult {
+        if self.surface.is_none() || self.window.inner_size().height < 10 {
+            return RenderResult::Ok;
+        }
+
+        self.apply_pending_bounding_box();
+        self.apply_pending_statistics();
+        if self.is_advancing() {
+            self.inform_ui_about_progress();
+        }
+
+        // Acquired before enqueueing generations, so that returning early cannot drop an
+        // encoder with generations and history snapshots that were counted but not submitted:
+        let Some(surface) = &self.surface else {
+            return RenderResult::Other;
+        };
+        let output = match surface.get_current_texture() {
             wgpu::CurrentSurfaceTexture::Success(texture)
             | wgpu::CurrentSurfaceTexture::Suboptimal(texture) => texture,
             wgpu::CurrentSurfaceTexture::Lost | wgpu::CurrentSurfaceTexture::Outdated => {
@@ -335,17 +1535,72 @@ impl State {
             }
         };
 
+        let mut encoder =
+            self.simulation
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("command_encoder_descriptor"),
+                });
+
+        let generations = if self.paused || self.fast_forward.is_some() {
+            self.last_time = web_time::Instant::now();
+            0
+        } else if self.turbo {
+            // Adjust the generations to the duration of the previous frame, which grows
+            // when the GPU does not keep up:
+            if self.last_time.elapsed().as_secs_f32() < Self::TURBO_FRAME_BUDGET {
+                self.turbo_generations =
+                    (self.turbo_generations * 5 / 4 + 1).min(Self::MAX_TURBO_GENERATIONS);
+            } else {
+                self.turbo_generations = (self.turbo_generations * 3 / 4).max(1);
+            }
+            self.last_time = web_time::Instant::now();
+            self.turbo_generations
+        } else {
+            let frequency = 1.0 / f32::from(self.generations_per_second);
+            self.elapsed_time += self.last_time.elapsed().as_secs_f32();
+            self.last_time = web_time::Instant::now();
+            let generations = (self.elapsed_time / frequency).floor();
+            self.elapsed_time -= generations * frequency;
+            generations as u64
+        };
+        self.enqueue_generations(&mut encoder, generations);
+
         self.renderer.enqueue(
-            self.computer.currently_computed_is_0,
+            self.simulation.computer().currently_computed_is_0,
+            self.selection.is_some(),
             &mut encoder,
             &output,
             &self.texture_view_descriptor,
         );
+        if self.show_population_graph {
+            self.population_graph.enqueue(
+                self.simulation.queue(),
+                &mut encoder,
+                &output,
+                &self.texture_view_descriptor,
+            );
+        }
 
         // submit will accept anything that implements IntoIter
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.simulation
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
         output.present();
 
+        if self.pending_statistics.is_none() {
+            self.request_statistics();
+        }
+
         RenderResult::Ok
     }
 }
+
+/// Run a future in the background on the web, and to completion on native platforms,
+/// where readbacks from the GPU are waited for and so are ready right away.
+fn spawn(future: impl std::future::Future<Output = ()> + 'static) {
+    #[cfg(target_family = "wasm")]
+    wasm_bindgen_futures::spawn_local(future);
+    #[cfg(not(target_family = "wasm"))]
+    pollster::block_on(future);
+}
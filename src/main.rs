@@ -1,13 +1,13 @@
 fn main() {
     #[cfg(not(any(target_os = "android", target_family = "wasm")))]
     {
-        use wgpu_game_of_life::event_loop::App;
+        use wgpu_game_of_life::event_loop::{App, UserEvent};
         use winit::event_loop::EventLoop;
 
         env_logger::init();
 
-        let event_loop = EventLoop::new().unwrap();
-        let mut app = App::new();
+        let event_loop = EventLoop::<UserEvent>::with_user_event().build().unwrap();
+        let mut app = App::new(&event_loop);
         event_loop.run_app(&mut app).unwrap();
     }
 }
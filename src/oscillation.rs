@@ -0,0 +1,197 @@
+//! Detection of the grid becoming static or periodic, by comparing hashes of the cells
+//! at the generations that statistics are read back at.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::computer::Statistics;
+
+/// The number of recent samples kept, which limits the periods that can be detected.
+const CAPACITY: usize = 4096;
+
+/// The grid repeating itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Oscillation {
+    /// The number of generations after which the cells repeat, which is 1 for a static grid.
+    ///
+    /// When not every generation has been sampled, such as in turbo mode, this starts out
+    /// as a multiple of the period and is narrowed down as more repetitions are seen.
+    pub period: u64,
+    /// The earliest generation sampled that was part of the cycle.
+    pub generation: u64,
+}
+
+/// What to do once the grid is static or periodic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OscillationAction {
+    #[default]
+    Report,
+    Pause,
+    /// Start over with a new random seed.
+    Reset,
+}
+
+impl OscillationAction {
+    pub const ALL: [Self; 3] = [Self::Report, Self::Pause, Self::Reset];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Report => "report",
+            Self::Pause => "pause",
+            Self::Reset => "reset",
+        }
+    }
+
+    pub const fn index(self) -> u32 {
+        self as u32
+    }
+
+    pub const fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Default)]
+pub struct OscillationDetector {
+    /// The generation and hash of samples, oldest first.
+    samples: VecDeque<(u64, u64)>,
+    /// The earliest generation of the samples with each hash.
+    generations: HashMap<u64, u64>,
+    oscillation: Option<Oscillation>,
+}
+
+impl OscillationDetector {
+    /// Forget the samples, such as when the cells have been edited.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub const fn oscillation(&self) -> Option<Oscillation> {
+        self.oscillation
+    }
+
+    /// Add the statistics of a generation, returning the oscillation when it is first
+    /// detected or its period has been narrowed down.
+    ///
+    /// Samples at the latest generation are ignored, while earlier generations start over.
+    pub fn record(&mut self, generation: u64, statistics: Statistics) -> Option<Oscillation> {
+        match self.samples.back() {
+            Some(&(latest_generation, _)) if generation == latest_generation => return None,
+            Some(&(latest_generation, _)) if generation < latest_generation => self.clear(),
+            _ => {}
+        }
+
+        let earlier_generation = self.generations.get(&statistics.hash).copied();
+        // Births and deaths are compared to the generation before, which is only known to
+        // have been computed from the cells sampled after the first sample:
+        let is_static =
+            !self.samples.is_empty() && statistics.births == 0 && statistics.deaths == 0;
+
+        if self.samples.len() == CAPACITY
+            && let Some((oldest_generation, oldest_hash)) = self.samples.pop_front()
+            && self.generations.get(&oldest_hash) == Some(&oldest_generation)
+        {
+            self.generations.remove(&oldest_hash);
+        }
+        self.samples.push_back((generation, statistics.hash));
+        self.generations
+            .entry(statistics.hash)
+            .or_insert(generation);
+
+        let detected = if is_static {
+            Oscillation {
+                period: 1,
+                generation: earlier_generation.unwrap_or(generation - 1),
+            }
+        } else {
+            let earlier_generation = earlier_generation?;
+            Oscillation {
+                period: generation - earlier_generation,
+                generation: earlier_generation,
+            }
+        };
+        let oscillation = self
+            .oscillation
+            .map_or(detected, |oscillation| Oscillation {
+                period: gcd(oscillation.period, detected.period),
+                generation: oscillation.generation.min(detected.generation),
+            });
+        if self.oscillation == Some(oscillation) {
+            return None;
+        }
+        self.oscillation = Some(oscillation);
+        Some(oscillation)
+    }
+}
+
+const fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(hash: u64, births: u32) -> Statistics {
+        Statistics {
+            population: 3,
+            births,
+            deaths: births,
+            hash,
+        }
+    }
+
+    #[test]
+    fn test_static() {
+        let mut detector = OscillationDetector::default();
+        // The first sample may compare to cells before an edit:
+        assert_eq!(detector.record(10, statistics(1, 0)), None);
+        assert_eq!(detector.record(11, statistics(2, 5)), None);
+        assert_eq!(
+            detector.record(15, statistics(3, 0)),
+            Some(Oscillation {
+                period: 1,
+                generation: 14
+            })
+        );
+        assert_eq!(detector.record(16, statistics(3, 0)), None);
+    }
+
+    #[test]
+    fn test_periodic() {
+        let mut detector = OscillationDetector::default();
+        // A period of 4 reached at generation 20, sampled with varying steps as in turbo mode:
+        let hash_at = |generation: u64| {
+            if generation < 20 {
+                generation
+            } else {
+                100 + generation % 4
+            }
+        };
+        let mut detected = Vec::new();
+        let mut generation = 0;
+        for step in [5, 6].into_iter().cycle().take(30) {
+            generation += step;
+            detected.extend(detector.record(generation, statistics(hash_at(generation), 2)));
+        }
+        assert!(detected.len() > 1);
+        assert!(
+            detected
+                .iter()
+                .all(|oscillation| oscillation.period % 4 == 0)
+        );
+        assert_eq!(
+            detector.oscillation(),
+            Some(Oscillation {
+                period: 4,
+                generation: 22
+            })
+        );
+
+        // Going back starts over:
+        assert_eq!(detector.record(50, statistics(hash_at(50), 2)), None);
+        assert_eq!(detector.oscillation(), None);
+    }
+}
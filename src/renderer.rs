@@ -1,11 +1,15 @@
-use crate::computer::Computer;
+use crate::computer::{CellStorage, Computer};
 use wgpu::util::DeviceExt;
 
 pub struct RendererFactory {
     bind_group_layout: wgpu::BindGroupLayout,
     pipeline_layout: wgpu::PipelineLayout,
+    packed_bind_group_layout: wgpu::BindGroupLayout,
+    packed_pipeline_layout: wgpu::PipelineLayout,
     shader: wgpu::ShaderModule,
     square_buffer: wgpu::Buffer,
+    pub(crate) selection_buffer: wgpu::Buffer,
+    pub(crate) camera_buffer: wgpu::Buffer,
 }
 
 impl RendererFactory {
@@ -13,16 +17,38 @@ impl RendererFactory {
         let shader = device.create_shader_module(wgpu::include_wgsl!("game-of-life.render.wgsl"));
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
             label: Some("bind_group_layout_render"),
         });
 
@@ -32,6 +58,28 @@ impl RendererFactory {
             immediate_size: 0,
         });
 
+        let packed_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("bind_group_layout_packed_render"),
+            });
+
+        let packed_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("packed_render_pipeline_layout"),
+                bind_group_layouts: &[Some(&bind_group_layout), Some(&packed_bind_group_layout)],
+                immediate_size: 0,
+            });
+
         let square_vertices = [0, 0, 0, 1, 1, 0, 1, 1];
         let square_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("square_buffer"),
@@ -39,11 +87,29 @@ impl RendererFactory {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
+        let selection_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("selection_buffer"),
+            size: (4 * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("camera_buffer"),
+            size: (4 * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         Self {
             shader,
             bind_group_layout,
             square_buffer,
             pipeline_layout,
+            packed_bind_group_layout,
+            packed_pipeline_layout,
+            selection_buffer,
+            camera_buffer,
         }
     }
 
@@ -51,7 +117,6 @@ impl RendererFactory {
         &self,
         device: &wgpu::Device,
         computer: &Computer,
-        size_buffer: &wgpu::Buffer,
         cells_width: u32,
         cells_height: u32,
         texture_format: wgpu::TextureFormat,
@@ -110,20 +175,86 @@ impl RendererFactory {
             cache: Default::default(),
         });
 
-        let entries = [wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                buffer: size_buffer,
-                offset: 0,
-                size: None,
+        let selection_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("selection_pipeline"),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                buffers: &[],
+                entry_point: Some("selection_vertex_main"),
+                module: &self.shader,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                entry_point: Some("selection_fragment_main"),
+                module: &self.shader,
+                targets: &[Some(texture_format.into())],
+                compilation_options: Default::default(),
             }),
-        }];
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: Default::default(),
+        });
+
+        let entries = [
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &computer.size_buffer,
+                    offset: 0,
+                    size: None,
+                }),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &self.selection_buffer,
+                    offset: 0,
+                    size: None,
+                }),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &self.camera_buffer,
+                    offset: 0,
+                    size: None,
+                }),
+            },
+        ];
         let size_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.bind_group_layout,
             entries: &entries,
             label: Some("size_bind_group"),
         });
 
+        let packed_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("packed_render_pipeline"),
+                layout: Some(&self.packed_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    buffers: &[],
+                    entry_point: Some("packed_vertex_main"),
+                    module: &self.shader,
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    entry_point: Some("packed_fragment_main"),
+                    module: &self.shader,
+                    targets: &[Some(texture_format.into())],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: Default::default(),
+            });
+
         let create_render_bundle = |cells_buffer: &wgpu::Buffer| {
             let mut render_bundle_encoder =
                 device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
@@ -131,11 +262,28 @@ impl RendererFactory {
                     color_formats: &[Some(texture_format)],
                     ..Default::default()
                 });
-            render_bundle_encoder.set_pipeline(&render_pipeline);
-            render_bundle_encoder.set_vertex_buffer(0, cells_buffer.slice(..));
-            render_bundle_encoder.set_vertex_buffer(1, self.square_buffer.slice(..));
             render_bundle_encoder.set_bind_group(0, &size_bind_group, &[]);
-            render_bundle_encoder.draw(0..4, 0..(cells_width * cells_height));
+            match computer.storage() {
+                CellStorage::Ages => {
+                    render_bundle_encoder.set_pipeline(&render_pipeline);
+                    render_bundle_encoder.set_vertex_buffer(0, cells_buffer.slice(..));
+                    render_bundle_encoder.set_vertex_buffer(1, self.square_buffer.slice(..));
+                    render_bundle_encoder.draw(0..4, 0..(cells_width * cells_height));
+                }
+                CellStorage::Bits => {
+                    let packed_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.packed_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: cells_buffer.as_entire_binding(),
+                        }],
+                        label: Some("packed_bind_group"),
+                    });
+                    render_bundle_encoder.set_pipeline(&packed_render_pipeline);
+                    render_bundle_encoder.set_bind_group(1, &packed_bind_group, &[]);
+                    render_bundle_encoder.draw(0..3, 0..1);
+                }
+            }
             render_bundle_encoder.finish(&wgpu::RenderBundleDescriptor::default())
         };
 
@@ -145,6 +293,8 @@ impl RendererFactory {
         Renderer {
             render_bundle_0,
             render_bundle_1,
+            selection_pipeline,
+            size_bind_group,
         }
     }
 }
@@ -152,12 +302,15 @@ impl RendererFactory {
 pub struct Renderer {
     render_bundle_0: wgpu::RenderBundle,
     render_bundle_1: wgpu::RenderBundle,
+    selection_pipeline: wgpu::RenderPipeline,
+    size_bind_group: wgpu::BindGroup,
 }
 
 impl Renderer {
     pub(crate) fn enqueue(
         &self,
         render_first_buffer: bool,
+        show_selection: bool,
         encoder: &mut wgpu::CommandEncoder,
         surface_texture: &wgpu::SurfaceTexture,
         texture_view_descriptor: &wgpu::TextureViewDescriptor,
@@ -187,5 +340,11 @@ impl Renderer {
         } else {
             &self.render_bundle_1
         }));
+
+        if show_selection {
+            render_pass.set_pipeline(&self.selection_pipeline);
+            render_pass.set_bind_group(0, &self.size_bind_group, &[]);
+            render_pass.draw(0..5, 0..1);
+        }
     }
 }
@@ -0,0 +1,183 @@
+//! The [Run Length Encoded](https://conwaylife.com/wiki/Run_Length_Encoded) pattern format.
+
+const MAX_LINE_LENGTH: usize = 70;
+
+/// A pattern decoded from RLE.
+pub(crate) struct Pattern {
+    /// The rule of the header, if given.
+    pub(crate) rulestring: Option<String>,
+    /// The live cells as `(x, y)` with `y = 0` at the top row, as in RLE.
+    pub(crate) cells: Vec<(u32, u32)>,
+}
+
+/// Encode a pattern of the given size, where `is_alive(x, y)` tells if a cell is alive.
+///
+/// Rows are encoded from `y = 0` and down, as RLE has the first row at the top.
+pub(crate) fn encode(
+    width: u32,
+    height: u32,
+    rulestring: &str,
+    is_alive: impl Fn(u32, u32) -> bool,
+) -> String {
+    let mut runs: Vec<(u32, char)> = Vec::new();
+    let mut push_run = |count: u32, tag: char| {
+        if count == 0 {
+            return;
+        }
+        match runs.last_mut() {
+            Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+            _ => runs.push((count, tag)),
+        }
+    };
+
+    for y in 0..height {
+        let mut dead_run = 0;
+        let mut x = 0;
+        while x < width {
+            let alive = is_alive(x, y);
+            let start = x;
+            while x < width && is_alive(x, y) == alive {
+                x += 1;
+            }
+            if alive {
+                push_run(dead_run, 'b');
+                dead_run = 0;
+                push_run(x - start, 'o');
+            } else {
+                // Trailing dead cells in a row are omitted.
+                dead_run = x - start;
+            }
+        }
+        if y + 1 < height {
+            push_run(1, '$');
+        }
+    }
+
+    // Trailing empty rows are omitted.
+    while runs.last().is_some_and(|&(_, tag)| tag == '$') {
+        runs.pop();
+    }
+
+    let mut result = format!("x = {width}, y = {height}, rule = {rulestring}\n");
+    let mut line_length = 0;
+    for token in runs
+        .iter()
+        .map(|&(count, tag)| {
+            if count == 1 {
+                tag.to_string()
+            } else {
+                format!("{count}{tag}")
+            }
+        })
+        .chain(std::iter::once(String::from("!")))
+    {
+        if line_length + token.len() > MAX_LINE_LENGTH {
+            result.push('\n');
+            line_length = 0;
+        }
+        line_length += token.len();
+        result.push_str(&token);
+    }
+    result.push('\n');
+    result
+}
+
+/// Decode a pattern, where any state other than `b` and `.` is taken as alive.
+pub(crate) fn decode(rle: &str) -> Result<Pattern, String> {
+    let mut rulestring = None;
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0_u32, 0_u32);
+    let mut count: Option<u32> = None;
+    'lines: for line in rle.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('x') {
+            rulestring = line.split(',').find_map(|field| {
+                let (key, value) = field.split_once('=')?;
+                (key.trim() == "rule").then(|| value.trim().to_string())
+            });
+            continue;
+        }
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit));
+                if count.is_none() {
+                    return Err("Too long run in RLE".to_string());
+                }
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            match c {
+                '!' => break 'lines,
+                '$' => {
+                    x = 0;
+                    y = y.saturating_add(run);
+                }
+                'b' | '.' => x = x.saturating_add(run),
+                c if c.is_ascii_alphabetic() => {
+                    cells.extend((0..run).map(|offset| (x.saturating_add(offset), y)));
+                    x = x.saturating_add(run);
+                }
+                c if c.is_whitespace() => {}
+                _ => return Err(format!("Invalid character in RLE: '{c}'")),
+            }
+        }
+    }
+    Ok(Pattern { rulestring, cells })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let glider = [
+            [false, true, false],
+            [false, false, true],
+            [true, true, true],
+        ];
+        assert_eq!(
+            encode(3, 3, "B3/S23", |x, y| glider[y as usize][x as usize]),
+            "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+
+        let blank_rows = [[true, false], [false, false], [false, false], [false, true]];
+        assert_eq!(
+            encode(2, 4, "B3/S23", |x, y| blank_rows[y as usize][x as usize]),
+            "x = 2, y = 4, rule = B3/S23\no3$bo!\n"
+        );
+
+        assert_eq!(
+            encode(4, 2, "B3/S23", |_, _| false),
+            "x = 4, y = 2, rule = B3/S23\n!\n"
+        );
+
+        let line = encode(200, 1, "B3/S23", |x, _| x % 2 == 0);
+        assert!(line.lines().all(|l| l.len() <= MAX_LINE_LENGTH));
+        assert_eq!(line.matches('o').count(), 100);
+    }
+
+    #[test]
+    fn test_decode() {
+        let pattern = decode("#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n").unwrap();
+        assert_eq!(pattern.rulestring.as_deref(), Some("B3/S23"));
+        assert_eq!(pattern.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+        let rows = [
+            [true, false, true, true],
+            [false; 4],
+            [false, true, false, false],
+        ];
+        let rle = encode(4, 3, "B36/S23", |x, y| rows[y as usize][x as usize]);
+        let pattern = decode(&rle).unwrap();
+        assert_eq!(pattern.rulestring.as_deref(), Some("B36/S23"));
+        assert_eq!(pattern.cells, [(0, 0), (2, 0), (3, 0), (1, 2)]);
+
+        assert!(decode("x = 1, y = 1\n2o?!").is_err());
+    }
+}
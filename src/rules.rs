@@ -1,3 +1,4 @@
+use crate::topology::Topology;
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::*;
 
@@ -17,18 +18,85 @@ impl Rule {
     }
 
     pub fn name(&self) -> String {
-        let mut born = String::from("B");
-        let mut survives = String::from("S");
-        for i in 0..9 {
-            if self.born & (1 << i) != 0 {
-                born.push_str(&format!("{i}"));
-            }
-            if self.survives & (1 << i) != 0 {
-                survives.push_str(&format!("{i}"));
-            }
+        format!("{} {}", self.name, self.rulestring())
+    }
+
+    /// The rule in B/S notation, such as `B3/S23` for Conway's Life.
+    pub fn rulestring(&self) -> String {
+        rulestring(self.born, self.survives)
+    }
+}
+
+/// The rule with the given neighbour counts in B/S notation.
+pub(crate) fn rulestring(born: u16, survives: u16) -> String {
+    let mut born_counts = String::from("B");
+    let mut survives_counts = String::from("S");
+    for i in 0..9 {
+        if born & (1 << i) != 0 {
+            born_counts.push_str(&format!("{i}"));
+        }
+        if survives & (1 << i) != 0 {
+            survives_counts.push_str(&format!("{i}"));
         }
-        format!("{} {}/{}", self.name, born, survives)
     }
+    format!("{born_counts}/{survives_counts}")
+}
+
+/// A rule parsed from a rulestring such as `B3/S23:P100,60`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParsedRule {
+    pub born: u16,
+    pub survives: u16,
+    pub topology: Topology,
+    /// The grid size given by the bounded grid suffix, if any.
+    pub size: Option<(u32, u32)>,
+}
+
+/// Parse a rulestring in `B3/S23` notation, or the older `23/3` survival/birth notation,
+/// optionally followed by a bounded grid suffix such as `:T100,50`.
+pub fn parse_rulestring(rulestring: &str) -> Result<ParsedRule, String> {
+    let invalid = || format!("Invalid rulestring: '{rulestring}'");
+    let (rule, suffix) = rulestring
+        .trim()
+        .split_once(':')
+        .map_or((rulestring.trim(), None), |(rule, suffix)| {
+            (rule, Some(suffix))
+        });
+    let (topology, size) = match suffix {
+        Some(suffix) => Topology::parse_suffix(suffix)?,
+        None => (Topology::default(), None),
+    };
+
+    let neighbour_counts = |digits: &str| -> Result<u16, String> {
+        digits
+            .chars()
+            .try_fold(0, |counts, digit| match digit.to_digit(10) {
+                Some(count) if count <= 8 => Ok(counts | (1 << count)),
+                _ => Err(invalid()),
+            })
+    };
+    let (first, second) = rule.split_once('/').ok_or_else(invalid)?;
+    let (born, survives) = match (
+        first.chars().next().map(|c| c.to_ascii_uppercase()),
+        second.chars().next().map(|c| c.to_ascii_uppercase()),
+    ) {
+        (Some('B'), Some('S')) => (
+            neighbour_counts(&first[1..])?,
+            neighbour_counts(&second[1..])?,
+        ),
+        (Some('S'), Some('B')) => (
+            neighbour_counts(&second[1..])?,
+            neighbour_counts(&first[1..])?,
+        ),
+        _ => (neighbour_counts(second)?, neighbour_counts(first)?),
+    };
+
+    Ok(ParsedRule {
+        born,
+        survives,
+        topology,
+        size,
+    })
 }
 
 pub static RULES: [Rule; 17] = [
@@ -147,3 +215,46 @@ pub fn get_rules() -> wasm_bindgen::prelude::JsValue {
             .collect::<js_sys::Array>(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rulestring() {
+        let conway = ParsedRule {
+            born: 0b1000,
+            survives: 0b1100,
+            topology: Topology::Torus,
+            size: None,
+        };
+        assert_eq!(parse_rulestring("B3/S23"), Ok(conway));
+        assert_eq!(parse_rulestring("s23/b3"), Ok(conway));
+        assert_eq!(parse_rulestring("23/3"), Ok(conway));
+        assert_eq!(
+            parse_rulestring("B3/S23:P100,60"),
+            Ok(ParsedRule {
+                topology: Topology::Plane,
+                size: Some((100, 60)),
+                ..conway
+            })
+        );
+        assert_eq!(
+            parse_rulestring("B2/S:K40,20*").map(|rule| (rule.born, rule.topology)),
+            Ok((
+                0b100,
+                Topology::KleinBottle {
+                    top_bottom_twisted: false
+                }
+            ))
+        );
+        assert!(parse_rulestring("B9/S23").is_err());
+        assert!(parse_rulestring("B3S23").is_err());
+        assert!(parse_rulestring("B3/S23:Q10").is_err());
+
+        for rule in &RULES {
+            let parsed = parse_rulestring(&rule.rulestring()).unwrap();
+            assert_eq!((parsed.born, parsed.survives), (rule.born, rule.survives));
+        }
+    }
+}
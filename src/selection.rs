@@ -0,0 +1,61 @@
+/// A rectangle of cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A rectangular selection spanned by dragging from `anchor` to `corner`.
+#[derive(Clone, Copy)]
+pub(crate) struct Selection {
+    pub(crate) anchor: (u32, u32),
+    pub(crate) corner: (u32, u32),
+}
+
+impl Selection {
+    pub(crate) fn rect(&self) -> Rect {
+        let x = self.anchor.0.min(self.corner.0);
+        let y = self.anchor.1.min(self.corner.1);
+        Rect {
+            x,
+            y,
+            width: self.anchor.0.max(self.corner.0) - x + 1,
+            height: self.anchor.1.max(self.corner.1) - y + 1,
+        }
+    }
+
+    /// The rectangles covering the grid outside the selection.
+    pub(crate) fn outside(&self, cells_width: u32, cells_height: u32) -> [Rect; 4] {
+        let rect = self.rect();
+        let above = rect.y + rect.height;
+        let right = rect.x + rect.width;
+        [
+            Rect {
+                x: 0,
+                y: 0,
+                width: cells_width,
+                height: rect.y,
+            },
+            Rect {
+                x: 0,
+                y: above,
+                width: cells_width,
+                height: cells_height - above,
+            },
+            Rect {
+                x: 0,
+                y: rect.y,
+                width: rect.x,
+                height: rect.height,
+            },
+            Rect {
+                x: right,
+                y: rect.y,
+                width: cells_width - right,
+                height: rect.height,
+            },
+        ]
+    }
+}
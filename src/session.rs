@@ -0,0 +1,262 @@
+//! A file format capturing a whole simulation, including the current cells.
+//!
+//! A session starts with the settings, stored little-endian after a magic
+//! header and a version, followed by the cells as they are stored on the GPU,
+//! compressed using zlib.
+
+use crate::computer::CellStorage;
+use crate::topology::Topology;
+
+const MAGIC: &[u8; 4] = b"GOLS";
+const VERSION: u8 = 1;
+
+/// The file that sessions are saved to and loaded from on desktop, while
+/// Android uses the internal storage of the app.
+#[cfg(not(any(target_os = "android", target_family = "wasm")))]
+const FILE_NAME: &str = "game-of-life.session";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Session {
+    /// The rulestring of the rule, without a boundary suffix.
+    pub rulestring: String,
+    pub topology: Topology,
+    pub cells_width: u32,
+    pub cells_height: u32,
+    pub cell_storage: CellStorage,
+    pub generation: u64,
+    pub seed: u32,
+    pub initial_density: u8,
+    pub generations_per_second: u8,
+    /// The cells laid out as in [`CellStorage::buffer_size()`].
+    pub words: Vec<u32>,
+}
+
+impl Session {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let rulestring_length = u8::try_from(self.rulestring.len())
+            .map_err(|_| format!("Too long rulestring for a session: {}", self.rulestring))?;
+        let mut bytes = Vec::from(*MAGIC);
+        bytes.push(VERSION);
+        bytes.push(match self.cell_storage {
+            CellStorage::Ages => 0,
+            CellStorage::Bits => 1,
+        });
+        bytes.push(self.topology.index() as u8);
+        bytes.push(rulestring_length);
+        bytes.extend_from_slice(self.rulestring.as_bytes());
+        bytes.extend_from_slice(&self.cells_width.to_le_bytes());
+        bytes.extend_from_slice(&self.cells_height.to_le_bytes());
+        bytes.extend_from_slice(&self.generation.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(self.initial_density);
+        bytes.push(self.generations_per_second);
+        bytes.extend_from_slice(&miniz_oxide::deflate::compress_to_vec_zlib(
+            bytemuck::cast_slice(&self.words),
+            6,
+        ));
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(bytes);
+        if reader.take::<4>()? != *MAGIC {
+            return Err("Not a session".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("Unsupported session version: {version}"));
+        }
+        let cell_storage = match reader.u8()? {
+            0 => CellStorage::Ages,
+            1 => CellStorage::Bits,
+            other => return Err(format!("Invalid cell storage in session: {other}")),
+        };
+        let topology_idx = reader.u8()?;
+        let topology = *Topology::ALL
+            .get(usize::from(topology_idx))
+            .ok_or_else(|| format!("Invalid boundary in session: {topology_idx}"))?;
+        let rulestring_length = usize::from(reader.u8()?);
+        let rulestring = String::from_utf8(reader.take_slice(rulestring_length)?.to_vec())
+            .map_err(|e| format!("Invalid rulestring in session: {e}"))?;
+        let cells_width = u32::from_le_bytes(reader.take()?);
+        let cells_height = u32::from_le_bytes(reader.take()?);
+        let generation = u64::from_le_bytes(reader.take()?);
+        let seed = u32::from_le_bytes(reader.take()?);
+        let initial_density = reader.u8()?;
+        let generations_per_second = reader.u8()?;
+        if !crate::State::GENERATIONS_PER_SECOND.contains(&generations_per_second) {
+            return Err(format!(
+                "Invalid generations per second in session: {generations_per_second}"
+            ));
+        }
+
+        // The dimensions are not validated yet, so the size can overflow:
+        let buffer_size = u64::from(cell_storage.words_per_row(cells_width))
+            .checked_mul(u64::from(cells_height))
+            .and_then(|words| words.checked_mul(std::mem::size_of::<u32>() as u64))
+            .and_then(|size| usize::try_from(size).ok())
+            .ok_or_else(|| format!("Too large grid in session: {cells_width}x{cells_height}"))?;
+        let cell_bytes =
+            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(reader.0, buffer_size)
+                .map_err(|e| format!("Invalid cells in session: {e}"))?;
+        if cell_bytes.len() != buffer_size {
+            return Err(format!(
+                "Expected {buffer_size} bytes of cells in session, got {}",
+                cell_bytes.len()
+            ));
+        }
+
+        Ok(Self {
+            rulestring,
+            topology,
+            cells_width,
+            cells_height,
+            cell_storage,
+            generation,
+            seed,
+            initial_density,
+            generations_per_second,
+            words: bytemuck::pod_collect_to_vec(&cell_bytes),
+        })
+    }
+}
+
+/// Reads fields from the start of a session, leaving the rest.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take_slice(&mut self, length: usize) -> Result<&[u8], String> {
+        let (taken, rest) = self
+            .0
+            .split_at_checked(length)
+            .ok_or_else(|| "Truncated session".to_string())?;
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let (taken, rest) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or_else(|| "Truncated session".to_string())?;
+        self.0 = rest;
+        Ok(*taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take::<1>()?[0])
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_family = "wasm")))]
+fn path() -> std::path::PathBuf {
+    std::path::PathBuf::from(FILE_NAME)
+}
+
+#[cfg(target_os = "android")]
+fn path() -> std::path::PathBuf {
+    crate::android::session_path()
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn store(bytes: &[u8]) {
+    let path = path();
+    match std::fs::write(&path, bytes) {
+        Ok(()) => log::info!("Saved session to {}", path.display()),
+        Err(e) => log::error!("Failed saving session to {}: {e}", path.display()),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn stored() -> Result<Vec<u8>, String> {
+    let path = path();
+    std::fs::read(&path).map_err(|e| format!("Failed loading session from {}: {e}", path.display()))
+}
+
+#[cfg(target_family = "wasm")]
+pub(crate) fn store(bytes: &[u8]) {
+    crate::web::store_session(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(cell_storage: CellStorage) -> Session {
+        let (cells_width, cells_height) = (70, 3);
+        let word_count = cell_storage.buffer_size(cells_width, cells_height) as usize / 4;
+        Session {
+            rulestring: "B36/S23".to_string(),
+            topology: Topology::ALL[2],
+            cells_width,
+            cells_height,
+            cell_storage,
+            generation: 1 << 40,
+            seed: 1234,
+            initial_density: 12,
+            generations_per_second: 30,
+            words: (0..word_count as u32).map(|i| i * i).collect(),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for cell_storage in [CellStorage::Ages, CellStorage::Bits] {
+            let session = session(cell_storage);
+            assert_eq!(
+                Session::from_bytes(&session.to_bytes().unwrap()),
+                Ok(session)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = session(CellStorage::Ages).to_bytes().unwrap();
+        assert_eq!(
+            Session::from_bytes(b"x = 3, y = 1\n3o!"),
+            Err("Not a session".to_string())
+        );
+        assert_eq!(
+            Session::from_bytes(&bytes[..10]),
+            Err("Truncated session".to_string())
+        );
+        assert!(Session::from_bytes(&bytes[..bytes.len() - 4]).is_err());
+
+        // A grid whose size in bytes overflows:
+        let mut too_large = bytes.clone();
+        too_large[15..23].fill(u8::MAX);
+        assert_eq!(
+            Session::from_bytes(&too_large),
+            Err(format!("Too large grid in session: {0}x{0}", u32::MAX))
+        );
+
+        // A speed outside of what can be set:
+        for generations_per_second in [0, 101] {
+            let mut invalid_speed = session(CellStorage::Ages);
+            invalid_speed.generations_per_second = generations_per_second;
+            assert_eq!(
+                Session::from_bytes(&invalid_speed.to_bytes().unwrap()),
+                Err(format!(
+                    "Invalid generations per second in session: {generations_per_second}"
+                ))
+            );
+        }
+
+        let mut future_version = bytes;
+        future_version[4] = 2;
+        assert_eq!(
+            Session::from_bytes(&future_version),
+            Err("Unsupported session version: 2".to_string())
+        );
+
+        // Cells that do not match the grid size:
+        let mut too_few_cells = session(CellStorage::Ages);
+        too_few_cells.words.pop();
+        assert!(Session::from_bytes(&too_few_cells.to_bytes().unwrap()).is_err());
+
+        let mut too_long_rulestring = session(CellStorage::Ages);
+        too_long_rulestring.rulestring = "B3/S23".repeat(50);
+        assert!(too_long_rulestring.to_bytes().is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! A simulation of a grid on the GPU without any window or surface, which [`crate::State`]
+//! renders and edits, and which can be embedded where there is no window.
+//!
+//! Tools that do not need the GPU buffers go through the [`Backend`] trait instead, which
+//! [`crate::cpu::CpuSimulation`] also implements for machines without a suitable adapter.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::computer::{self, CellStorage, Cells, Computer, ComputerFactory, Statistics};
+use crate::hashlife::HashLife;
+use crate::rules::{self, Rule};
+use crate::selection::Rect;
+use crate::topology::Topology;
+
+/// What a simulation is started from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// The index of the rule in [`rules::RULES`].
+    pub rule_idx: u32,
+    pub topology: Topology,
+    pub cells_width: u32,
+    pub cells_height: u32,
+    pub cell_storage: CellStorage,
+    /// The seed of the random initial cells.
+    pub seed: u32,
+    /// The percentage of cells initially alive.
+    pub initial_density: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rule_idx: 0,
+            topology: Topology::default(),
+            cells_width: 512,
+            cells_height: 512,
+            cell_storage: CellStorage::default(),
+            seed: 0,
+            initial_density: rules::RULES[0].initial_density,
+        }
+    }
+}
+
+impl Settings {
+    /// The rule, which must have been validated by [`Self::validate()`].
+    pub fn rule(&self) -> &'static Rule {
+        &rules::RULES[self.rule_idx as usize]
+    }
+
+    /// The rule including the bounded grid suffix, such as `B3/S23:T512,512`.
+    pub fn rulestring(&self) -> String {
+        self.rule().rulestring() + &self.topology.suffix(self.cells_width, self.cells_height)
+    }
+
+    /// Check that the settings are supported on a device with the given limits.
+    pub fn validate(&self, limits: &wgpu::Limits) -> Result<(), String> {
+        computer::validate_size(
+            limits,
+            self.cells_width,
+            self.cells_height,
+            self.cell_storage,
+        )?;
+        self.validate_without_limits()
+    }
+
+    /// Check the settings apart from the grid size fitting on a device, for simulating on the CPU.
+    pub(crate) fn validate_without_limits(&self) -> Result<(), String> {
+        if self.rule_idx as usize >= rules::RULES.len() {
+            return Err(format!("Invalid rule index: {}", self.rule_idx));
+        }
+        if !(1..=99).contains(&self.initial_density) {
+            return Err(format!("Invalid initial density: {}", self.initial_density));
+        }
+        if self.cells_width == 0 || self.cells_height == 0 {
+            return Err(format!(
+                "Invalid grid size {}x{}",
+                self.cells_width, self.cells_height
+            ));
+        }
+        if self
+            .topology
+            .supports_size(self.cells_width, self.cells_height)
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} does not support grid size {}x{}",
+                self.topology.name(),
+                self.cells_width,
+                self.cells_height
+            ))
+        }
+    }
+}
+
+/// Request a device to simulate on, with the limits of the adapter, able to present to
+/// `compatible_surface` if given.
+pub async fn request_device(
+    instance: &wgpu::Instance,
+    compatible_surface: Option<&wgpu::Surface<'_>>,
+    force_fallback_adapter: bool,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), String> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface,
+            force_fallback_adapter,
+        })
+        .await
+        .map_err(|e| format!("request_adapter failed: {e}"))?;
+
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+            label: None,
+            memory_hints: Default::default(),
+            trace: Default::default(),
+            experimental_features: Default::default(),
+        })
+        .await
+        .map_err(|e| format!("request_device failed: {e}"))?;
+    Ok((adapter, device, queue))
+}
+
+pub struct Simulation {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    computer_factory: ComputerFactory,
+    computer: Computer,
+    settings: Settings,
+    generation: u64,
+}
+
+impl Simulation {
+    pub fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        settings: Settings,
+    ) -> Result<Self, String> {
+        let computer_factory = ComputerFactory::new(&device);
+        settings.validate(&device.limits())?;
+        let computer = computer_factory.create(
+            &device,
+            settings.cells_width,
+            settings.cells_height,
+            settings.cell_storage,
+            settings.rule(),
+            settings.topology,
+            settings.seed,
+            settings.initial_density,
+        )?;
+        Ok(Self {
+            device,
+            queue,
+            computer_factory,
+            computer,
+            settings,
+            generation: 0,
+        })
+    }
+
+    /// Create a simulation on a device of its own, preferring a high performance adapter.
+    pub async fn new_headless(settings: Settings) -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let (_adapter, device, queue) = request_device(&instance, None, false).await?;
+        Self::new(device, queue, settings)
+    }
+
+    pub const fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub const fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    pub const fn computer(&self) -> &Computer {
+        &self.computer
+    }
+
+    pub(crate) const fn computer_mut(&mut self) -> &mut Computer {
+        &mut self.computer
+    }
+
+    pub const fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// The number of generations computed since the simulation was last reset.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Set the generation of the current cells, such as after restoring earlier cells.
+    pub(crate) const fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Start over from random cells with new settings, keeping the current simulation
+    /// if the settings are not supported.
+    pub fn reset(&mut self, settings: Settings) -> Result<(), String> {
+        settings.validate(&self.device.limits())?;
+        let computer = self.create_computer(settings, settings.initial_density)?;
+        self.replace_computer(settings, computer, 0);
+        Ok(())
+    }
+
+    /// Continue from cells laid out as stored in the cell buffers, such as ones saved in a
+    /// [`crate::session::Session`], at the given generation.
+    pub fn restore(
+        &mut self,
+        settings: Settings,
+        generation: u64,
+        words: &[u32],
+    ) -> Result<(), String> {
+        settings.validate(&self.device.limits())?;
+        let expected_size = settings
+            .cell_storage
+            .buffer_size(settings.cells_width, settings.cells_height);
+        if std::mem::size_of_val(words) as u64 != expected_size {
+            return Err(format!(
+                "Expected {expected_size} bytes of cells, got {}",
+                std::mem::size_of_val(words)
+            ));
+        }
+        let computer = self.create_computer(settings, 0)?;
+        computer.write_words(&self.queue, words);
+        self.replace_computer(settings, computer, generation);
+        Ok(())
+    }
+
+    /// Create a computer for the settings, with cells randomized using `initial_density`.
+    pub(crate) fn create_computer(
+        &self,
+        settings: Settings,
+        initial_density: u8,
+    ) -> Result<Computer, String> {
+        self.computer_factory.create(
+            &self.device,
+            settings.cells_width,
+            settings.cells_height,
+            settings.cell_storage,
+            settings.rule(),
+            settings.topology,
+            settings.seed,
+            initial_density,
+        )
+    }
+
+    /// Continue from the cells of a computer created by [`Self::create_computer()`].
+    pub(crate) fn replace_computer(
+        &mut self,
+        settings: Settings,
+        computer: Computer,
+        generation: u64,
+    ) {
+        self.settings = settings;
+        self.computer = computer;
+        self.generation = generation;
+    }
+
+    /// Advance generations, computed once `encoder` has been submitted.
+    pub fn enqueue_generations(&mut self, encoder: &mut wgpu::CommandEncoder, generations: u64) {
+        self.computer.enqueue_generations(encoder, generations);
+        self.generation += generations;
+    }
+
+    /// Advance generations, submitted right away.
+    pub fn step(&mut self, generations: u64) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("simulation_step_encoder"),
+            });
+        self.enqueue_generations(&mut encoder, generations);
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Read back the current generation, see [`Computer::read_cells()`].
+    pub fn read_cells(&self) -> impl Future<Output = Result<Cells, String>> + use<> {
+        self.computer.read_cells(&self.device, &self.queue)
+    }
+
+    /// Count and hash the cells of the current generation, see [`Computer::statistics()`].
+    pub fn statistics(&self, on_read: impl FnOnce(Statistics) + wgpu::WasmNotSend + 'static) {
+        self.computer.statistics(&self.device, &self.queue, on_read);
+    }
+
+    /// Write a rectangle of cells, see [`Computer::write_cells()`].
+    pub fn write_cells(&self, rect: Rect, values: &[u32]) -> Result<(), String> {
+        self.computer
+            .write_cells(&self.device, &self.queue, rect, values)
+    }
+
+    /// Set a cell to 0 for a dead cell or the age of a live cell, see [`Computer::set_cell()`].
+    pub fn set_cell(&self, x: u32, y: u32, value: u32) -> Result<(), String> {
+        self.computer
+            .set_cell(&self.device, &self.queue, x, y, value)
+    }
+
+    /// Set several cells to the same value in one submission, see [`Computer::set_cells()`].
+    pub fn set_cells(&self, cells: &[(u32, u32)], value: u32) -> Result<(), String> {
+        self.computer
+            .set_cells(&self.device, &self.queue, cells, value)
+    }
+
+    /// Show the window of a [`HashLife`] plane with its lower left cell at `(left, bottom)`,
+    /// continuing from the generation of the plane.
+    ///
+    /// Advancing further uses the rule and boundary of the settings, which cut off the
+    /// window from the rest of the plane.
+    pub fn show_hashlife(&mut self, hashlife: &HashLife, left: i64, bottom: i64) {
+        let words = hashlife.window_words(
+            left,
+            bottom,
+            self.settings.cells_width,
+            self.settings.cells_height,
+            self.settings.cell_storage,
+        );
+        self.computer.write_words(&self.queue, &words);
+        self.generation = hashlife.generation();
+    }
+}
+
+/// A boxed future, so that [`Backend`] can be used as a trait object.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// A simulation on the GPU or the CPU, for code that works with either, such as comparing them.
+pub trait Backend {
+    fn settings(&self) -> Settings;
+
+    /// The number of generations computed since the simulation was started.
+    fn generation(&self) -> u64;
+
+    /// Advance generations.
+    fn advance(&mut self, generations: u64);
+
+    /// Write a rectangle of cells, where `values` holds the rows of the rectangle with
+    /// 0 for dead cells and the age of live cells.
+    fn write_cells(&mut self, rect: Rect, values: &[u32]) -> Result<(), String>;
+
+    /// Read back the current generation.
+    fn fetch_cells(&self) -> BoxFuture<'_, Result<Cells, String>>;
+
+    /// Count and hash the cells of the current generation, see [`Computer::statistics()`].
+    fn fetch_statistics(&self) -> BoxFuture<'_, Result<Statistics, String>>;
+}
+
+impl Backend for Simulation {
+    fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn advance(&mut self, generations: u64) {
+        self.step(generations);
+    }
+
+    fn write_cells(&mut self, rect: Rect, values: &[u32]) -> Result<(), String> {
+        Self::write_cells(self, rect, values)
+    }
+
+    fn fetch_cells(&self) -> BoxFuture<'_, Result<Cells, String>> {
+        Box::pin(self.read_cells())
+    }
+
+    fn fetch_statistics(&self) -> BoxFuture<'_, Result<Statistics, String>> {
+        Box::pin(self.computer.statistics_async(&self.device, &self.queue))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_cells(simulation: &Simulation) -> Cells {
+        pollster::block_on(simulation.read_cells()).unwrap()
+    }
+
+    #[test]
+    fn test_simulation() {
+        let settings = Settings {
+            cells_width: 16,
+            cells_height: 8,
+            cell_storage: CellStorage::Bits,
+            ..Settings::default()
+        };
+        let mut simulation = pollster::block_on(Simulation::new_headless(settings)).unwrap();
+        simulation
+            .write_cells(
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 16,
+                    height: 8,
+                },
+                &[0; 16 * 8],
+            )
+            .unwrap();
+        // A horizontal blinker:
+        simulation
+            .write_cells(
+                Rect {
+                    x: 4,
+                    y: 3,
+                    width: 3,
+                    height: 1,
+                },
+                &[1, 1, 1],
+            )
+            .unwrap();
+
+        simulation.step(1);
+        assert_eq!(simulation.generation(), 1);
+        let cells = read_cells(&simulation);
+        let live_cells: Vec<(u32, u32)> = (0..8)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .filter(|&(x, y)| cells.get(x, y) > 0)
+            .collect();
+        assert_eq!(live_cells, [(5, 2), (5, 3), (5, 4)]);
+
+        assert!(
+            simulation
+                .write_cells(
+                    Rect {
+                        x: 15,
+                        y: 0,
+                        width: 2,
+                        height: 1,
+                    },
+                    &[1, 1],
+                )
+                .is_err()
+        );
+
+        // Unsupported settings keep the simulation:
+        let invalid_settings = Settings {
+            cells_width: 0,
+            ..settings
+        };
+        assert!(simulation.reset(invalid_settings).is_err());
+        assert_eq!(simulation.settings(), settings);
+        assert_eq!(simulation.generation(), 1);
+
+        let words = read_cells(&simulation).into_words();
+        simulation.reset(settings).unwrap();
+        assert_eq!(simulation.generation(), 0);
+        simulation.restore(settings, 1, &words).unwrap();
+        assert_eq!(simulation.generation(), 1);
+        assert_eq!(read_cells(&simulation).into_words(), words);
+
+        // The vertical blinker with its top cell at (0, 0) of the plane:
+        let hashlife = HashLife::from_rle("x = 1, y = 3\no$o$o!").unwrap();
+        simulation.show_hashlife(&hashlife, -5, -4);
+        assert_eq!(read_cells(&simulation).into_words(), words);
+    }
+}
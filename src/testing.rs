@@ -0,0 +1,17 @@
+//! Helpers shared by tests that run on the GPU.
+
+use crate::computer::{Cells, Computer};
+
+/// A device of the default adapter.
+pub(crate) fn device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .unwrap();
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).unwrap()
+}
+
+/// Read back the current generation of `computer`, blocking until it is available.
+pub(crate) fn read_cells(device: &wgpu::Device, queue: &wgpu::Queue, computer: &Computer) -> Cells {
+    pollster::block_on(computer.read_cells(device, queue)).unwrap()
+}
@@ -0,0 +1,232 @@
+//! How the edges of the grid are joined, matching the
+//! [bounded grids](https://golly.sourceforge.io/Help/bounded.html) of Golly.
+
+/// The shape of the grid, as given by the `:T`, `:P`, `:K`, `:C` and `:S` rulestring suffixes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Opposite edges are joined, so patterns wrap around.
+    #[default]
+    Torus,
+    /// Cells outside the edges are always dead.
+    Plane,
+    /// Like a torus, but one pair of opposite edges is joined with a twist, so that patterns
+    /// leaving through one of them come back mirrored. These are the top and bottom edges if
+    /// `top_bottom_twisted`, and the left and right edges otherwise.
+    KleinBottle { top_bottom_twisted: bool },
+    /// Both pairs of opposite edges are joined with a twist.
+    CrossSurface,
+    /// The top edge is joined to the left edge, and the bottom edge to the right edge.
+    /// This requires a square grid.
+    Sphere,
+}
+
+impl Topology {
+    pub const ALL: [Self; 6] = [
+        Self::Torus,
+        Self::Plane,
+        Self::KleinBottle {
+            top_bottom_twisted: true,
+        },
+        Self::KleinBottle {
+            top_bottom_twisted: false,
+        },
+        Self::CrossSurface,
+        Self::Sphere,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Torus => "Torus",
+            Self::Plane => "Plane",
+            Self::KleinBottle {
+                top_bottom_twisted: true,
+            } => "Klein bottle (top/bottom twisted)",
+            Self::KleinBottle {
+                top_bottom_twisted: false,
+            } => "Klein bottle (left/right twisted)",
+            Self::CrossSurface => "Cross-surface",
+            Self::Sphere => "Sphere",
+        }
+    }
+
+    /// The index of this topology in [`Self::ALL`].
+    pub fn index(self) -> u32 {
+        Self::ALL
+            .iter()
+            .position(|&topology| topology == self)
+            .unwrap_or_default() as u32
+    }
+
+    pub fn supports_size(self, cells_width: u32, cells_height: u32) -> bool {
+        self != Self::Sphere || cells_width == cells_height
+    }
+
+    /// The rulestring suffix for a grid of the given size, such as `:K40*,20`.
+    pub fn suffix(self, cells_width: u32, cells_height: u32) -> String {
+        match self {
+            Self::Torus => format!(":T{cells_width},{cells_height}"),
+            Self::Plane => format!(":P{cells_width},{cells_height}"),
+            Self::KleinBottle {
+                top_bottom_twisted: true,
+            } => format!(":K{cells_width}*,{cells_height}"),
+            Self::KleinBottle {
+                top_bottom_twisted: false,
+            } => format!(":K{cells_width},{cells_height}*"),
+            Self::CrossSurface => format!(":C{cells_width},{cells_height}"),
+            Self::Sphere => format!(":S{cells_width}"),
+        }
+    }
+
+    /// Parse a rulestring suffix without the leading `:`, such as `T100,50` or `K40*,20`,
+    /// into the topology and the grid size if given.
+    pub fn parse_suffix(suffix: &str) -> Result<(Self, Option<(u32, u32)>), String> {
+        let invalid = || format!("Invalid bounded grid: '{suffix}'");
+        let mut chars = suffix.chars();
+        let kind = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+        let dimensions = chars.as_str();
+        if dimensions.is_empty() {
+            return match kind {
+                'T' => Ok((Self::Torus, None)),
+                'P' => Ok((Self::Plane, None)),
+                'C' => Ok((Self::CrossSurface, None)),
+                'S' => Ok((Self::Sphere, None)),
+                _ => Err(invalid()),
+            };
+        }
+
+        let parse_dimension = |dimension: &str| -> Result<(u32, bool), String> {
+            let (dimension, twisted) = dimension
+                .strip_suffix('*')
+                .map_or((dimension, false), |dimension| (dimension, true));
+            match dimension.parse::<u32>() {
+                Ok(size) if size > 0 => Ok((size, twisted)),
+                _ => Err(invalid()),
+            }
+        };
+        let ((width, width_twisted), (height, height_twisted)) =
+            if let Some((width, height)) = dimensions.split_once(',') {
+                (parse_dimension(width)?, parse_dimension(height)?)
+            } else {
+                let dimension = parse_dimension(dimensions)?;
+                (dimension, dimension)
+            };
+
+        let topology = match (kind, width_twisted, height_twisted) {
+            ('T', false, false) => Self::Torus,
+            ('P', false, false) => Self::Plane,
+            ('K', true, false) => Self::KleinBottle {
+                top_bottom_twisted: true,
+            },
+            ('K', false, true) => Self::KleinBottle {
+                top_bottom_twisted: false,
+            },
+            ('C', false, false) => Self::CrossSurface,
+            ('S', false, false) if width == height => Self::Sphere,
+            _ => return Err(invalid()),
+        };
+        Ok((topology, Some((width, height))))
+    }
+
+    /// The cell at `(x, y)`, which may be up to one cell outside the grid, or `None` if that
+    /// is outside a plane or diagonally outside a corner of a cross-surface or sphere.
+    ///
+    /// This is the same mapping as done by the compute shader.
+    pub fn cell_at(
+        self,
+        x: i32,
+        y: i32,
+        cells_width: u32,
+        cells_height: u32,
+    ) -> Option<(u32, u32)> {
+        let (w, h) = (cells_width as i32, cells_height as i32);
+        let outside_x = x < 0 || x >= w;
+        let outside_y = y < 0 || y >= h;
+        let (x, y) = if !outside_x && !outside_y {
+            (x, y)
+        } else {
+            match self {
+                Self::Plane => return None,
+                Self::CrossSurface | Self::Sphere if outside_x && outside_y => return None,
+                Self::Sphere if y >= h => (0, h - 1 - x),
+                Self::Sphere if x < 0 => (h - 1 - y, h - 1),
+                Self::Sphere if y < 0 => (w - 1, h - 1 - x),
+                Self::Sphere => (h - 1 - y, 0),
+                Self::Torus | Self::KleinBottle { .. } | Self::CrossSurface => {
+                    let [_, _, top_bottom_twisted, left_right_twisted] = self.uniform_array();
+                    (
+                        if outside_y && top_bottom_twisted == 1 {
+                            w - 1 - x
+                        } else {
+                            x
+                        },
+                        if outside_x && left_right_twisted == 1 {
+                            h - 1 - y
+                        } else {
+                            y
+                        },
+                    )
+                }
+            }
+        };
+        Some((x.rem_euclid(w) as u32, y.rem_euclid(h) as u32))
+    }
+
+    /// The topology uniform used by the compute shader, as
+    /// `(is_plane, is_sphere, top_bottom_twisted, left_right_twisted)`.
+    pub(crate) const fn uniform_array(self) -> [u32; 4] {
+        match self {
+            Self::Torus => [0, 0, 0, 0],
+            Self::Plane => [1, 0, 0, 0],
+            Self::KleinBottle { top_bottom_twisted } => {
+                [0, 0, top_bottom_twisted as u32, !top_bottom_twisted as u32]
+            }
+            Self::CrossSurface => [0, 0, 1, 1],
+            Self::Sphere => [0, 1, 0, 0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suffix() {
+        assert_eq!(
+            Topology::parse_suffix("T100,50"),
+            Ok((Topology::Torus, Some((100, 50))))
+        );
+        assert_eq!(
+            Topology::parse_suffix("p64"),
+            Ok((Topology::Plane, Some((64, 64))))
+        );
+        assert_eq!(
+            Topology::parse_suffix("K40*,20"),
+            Ok((
+                Topology::KleinBottle {
+                    top_bottom_twisted: true
+                },
+                Some((40, 20))
+            ))
+        );
+        assert_eq!(
+            Topology::parse_suffix("C"),
+            Ok((Topology::CrossSurface, None))
+        );
+        assert_eq!(
+            Topology::parse_suffix("S30"),
+            Ok((Topology::Sphere, Some((30, 30))))
+        );
+        assert!(Topology::parse_suffix("S30,20").is_err());
+        assert!(Topology::parse_suffix("K40,20").is_err());
+        assert!(Topology::parse_suffix("T0,20").is_err());
+        assert!(Topology::parse_suffix("X10").is_err());
+
+        for topology in Topology::ALL {
+            assert_eq!(
+                Topology::parse_suffix(&topology.suffix(30, 30)[1..]),
+                Ok((topology, Some((30, 30))))
+            );
+        }
+    }
+}
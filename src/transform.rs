@@ -0,0 +1,583 @@
+use crate::computer::{CellStorage, WORKGROUP_WIDTH};
+use crate::selection::Rect;
+
+/// An integer affine transform of the grid, mapping each destination cell to the
+/// source cell at `matrix * destination + offset`, wrapping around the source edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transform {
+    matrix: [i32; 4],
+    offset: [i32; 2],
+}
+
+impl Transform {
+    /// Rotate a grid of the given size clockwise by a number of quarter turns.
+    pub const fn rotate_clockwise(quarter_turns: u32, cells_width: u32, cells_height: u32) -> Self {
+        let (w, h) = (cells_width as i32, cells_height as i32);
+        match quarter_turns % 4 {
+            0 => Self::translate(0, 0),
+            1 => Self {
+                matrix: [0, -1, 1, 0],
+                offset: [w - 1, 0],
+            },
+            2 => Self {
+                matrix: [-1, 0, 0, -1],
+                offset: [w - 1, h - 1],
+            },
+            _ => Self {
+                matrix: [0, 1, -1, 0],
+                offset: [0, h - 1],
+            },
+        }
+    }
+
+    /// Mirror a grid of the given width, so that the left edge becomes the right edge.
+    pub const fn mirror_horizontally(cells_width: u32) -> Self {
+        Self {
+            matrix: [-1, 0, 0, 1],
+            offset: [cells_width as i32 - 1, 0],
+        }
+    }
+
+    /// Mirror a grid of the given height, so that the bottom edge becomes the top edge.
+    pub const fn mirror_vertically(cells_height: u32) -> Self {
+        Self {
+            matrix: [1, 0, 0, -1],
+            offset: [0, cells_height as i32 - 1],
+        }
+    }
+
+    /// Move all cells by the given offset, wrapping around the edges.
+    pub const fn translate(dx: i32, dy: i32) -> Self {
+        Self {
+            matrix: [1, 0, 0, 1],
+            offset: [-dx, -dy],
+        }
+    }
+
+    /// The size of the destination grid when transforming a source grid of the given size.
+    pub const fn destination_size(&self, cells_width: u32, cells_height: u32) -> (u32, u32) {
+        if self.matrix[0] == 0 {
+            (cells_height, cells_width)
+        } else {
+            (cells_width, cells_height)
+        }
+    }
+
+    fn uniform_array(&self, source_size: (u32, u32), destination_size: (u32, u32)) -> [i32; 12] {
+        [
+            self.matrix[0],
+            self.matrix[1],
+            self.matrix[2],
+            self.matrix[3],
+            self.offset[0],
+            self.offset[1],
+            source_size.0 as i32,
+            source_size.1 as i32,
+            destination_size.0 as i32,
+            destination_size.1 as i32,
+            0,
+            0,
+        ]
+    }
+}
+
+pub struct Transformer {
+    bind_group_layout: wgpu::BindGroupLayout,
+    bounding_box_bind_group_layout: wgpu::BindGroupLayout,
+    bounding_box_pipeline: wgpu::ComputePipeline,
+    bounding_box_bits_pipeline: wgpu::ComputePipeline,
+    pipeline: wgpu::ComputePipeline,
+    bits_pipeline: wgpu::ComputePipeline,
+}
+
+impl Transformer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader =
+            device.create_shader_module(wgpu::include_wgsl!("game-of-life.transform.wgsl"));
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                uniform_entry(2),
+            ],
+            label: Some("transform_bind_group_layout"),
+        });
+        let bounding_box_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    storage_entry(0, true),
+                    storage_entry(1, false),
+                    uniform_entry(2),
+                ],
+                label: Some("bounding_box_bind_group_layout"),
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("transform_pipeline_layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("transform_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: Default::default(),
+        });
+        let bits_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("transform_bits_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main_bits"),
+            compilation_options: Default::default(),
+            cache: Default::default(),
+        });
+
+        let bounding_box_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("bounding_box_pipeline_layout"),
+                bind_group_layouts: &[None, Some(&bounding_box_bind_group_layout)],
+                immediate_size: 0,
+            });
+        let bounding_box_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("bounding_box_pipeline"),
+                layout: Some(&bounding_box_pipeline_layout),
+                module: &shader,
+                entry_point: Some("bounding_box"),
+                compilation_options: Default::default(),
+                cache: Default::default(),
+            });
+        let bounding_box_bits_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("bounding_box_bits_pipeline"),
+                layout: Some(&bounding_box_pipeline_layout),
+                module: &shader,
+                entry_point: Some("bounding_box_bits"),
+                compilation_options: Default::default(),
+                cache: Default::default(),
+            });
+
+        Self {
+            bind_group_layout,
+            bounding_box_bind_group_layout,
+            bounding_box_pipeline,
+            bounding_box_bits_pipeline,
+            pipeline,
+            bits_pipeline,
+        }
+    }
+
+    /// Transform the `source` grid into the `destination` grid, which may be of a different size.
+    ///
+    /// Fails without enqueuing anything if a buffer is too small for the size of its grid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn enqueue(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        transform: &Transform,
+        storage: CellStorage,
+        source: &wgpu::Buffer,
+        source_size: (u32, u32),
+        destination: &wgpu::Buffer,
+        destination_size: (u32, u32),
+    ) -> Result<(), String> {
+        use wgpu::util::DeviceExt;
+
+        for (name, buffer, (cells_width, cells_height)) in [
+            ("source", source, source_size),
+            ("destination", destination, destination_size),
+        ] {
+            if buffer.size() < storage.buffer_size(cells_width, cells_height) {
+                return Err(format!(
+                    "The {name} buffer of {} bytes is too small for a {cells_width}x{cells_height} grid",
+                    buffer.size()
+                ));
+            }
+        }
+
+        // A buffer for each transform, so that several can be enqueued in a submission:
+        let transform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("transform_buffer"),
+            contents: bytemuck::cast_slice(&transform.uniform_array(source_size, destination_size)),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: source.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: destination.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: transform_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("transform_bind_group"),
+        });
+
+        let mut pass_encoder = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("transform_pass"),
+            timestamp_writes: None,
+        });
+        pass_encoder.set_pipeline(match storage {
+            CellStorage::Ages => &self.pipeline,
+            CellStorage::Bits => &self.bits_pipeline,
+        });
+        pass_encoder.set_bind_group(0, &bind_group, &[]);
+        pass_encoder.dispatch_workgroups(
+            storage
+                .words_per_row(destination_size.0)
+                .div_ceil(WORKGROUP_WIDTH),
+            destination_size.1.div_ceil(WORKGROUP_WIDTH),
+            1,
+        );
+        Ok(())
+    }
+
+    /// Compute the bounding box of live cells, calling `on_read` with it once available.
+    ///
+    /// The bounding box is `None` if there are no live cells. On native platforms this
+    /// waits for the GPU, so `on_read` has been called on return.
+    pub fn read_bounding_box(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        storage: CellStorage,
+        cells: &wgpu::Buffer,
+        size: (u32, u32),
+        on_read: impl FnOnce(Option<Rect>) + wgpu::WasmNotSend + 'static,
+    ) {
+        use wgpu::util::DeviceExt;
+
+        let size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("bounding_box_size_buffer"),
+            contents: bytemuck::cast_slice(&[size.0, size.1]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bounds_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("bounds_buffer"),
+            contents: bytemuck::cast_slice(&[u32::MAX, u32::MAX, 0, 0]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bounds_read_buffer"),
+            size: bounds_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bounding_box_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: cells.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: bounds_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: size_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("bounding_box_bind_group"),
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("bounding_box_encoder"),
+        });
+        {
+            let mut pass_encoder = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("bounding_box_pass"),
+                timestamp_writes: None,
+            });
+            pass_encoder.set_pipeline(match storage {
+                CellStorage::Ages => &self.bounding_box_pipeline,
+                CellStorage::Bits => &self.bounding_box_bits_pipeline,
+            });
+            pass_encoder.set_bind_group(1, &bind_group, &[]);
+            pass_encoder.dispatch_workgroups(
+                storage.words_per_row(size.0).div_ceil(WORKGROUP_WIDTH),
+                size.1.div_ceil(WORKGROUP_WIDTH),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&bounds_buffer, 0, &read_buffer, 0, read_buffer.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let mapped_buffer = read_buffer.clone();
+        read_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Err(e) = result {
+                    log::error!("Failed reading bounding box: {e}");
+                    return;
+                }
+                let bounds: [u32; 4] =
+                    bytemuck::pod_read_unaligned(&mapped_buffer.slice(..).get_mapped_range());
+                mapped_buffer.unmap();
+                let [min_x, min_y, max_x, max_y] = bounds;
+                on_read((min_x <= max_x).then(|| Rect {
+                    x: min_x,
+                    y: min_y,
+                    width: max_x - min_x + 1,
+                    height: max_y - min_y + 1,
+                }));
+            });
+
+        #[cfg(not(target_family = "wasm"))]
+        if let Err(e) = device.poll(wgpu::PollType::wait_indefinitely()) {
+            log::error!("Failed waiting for bounding box: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// Pack cells into rows of bits, with the cells that are odd numbers alive.
+    fn pack_bits(cells: &[u32], cells_width: usize) -> Vec<u32> {
+        let words_per_row = cells_width.div_ceil(32);
+        let mut words = vec![0; words_per_row * cells.len() / cells_width];
+        for (i, cell) in cells.iter().enumerate() {
+            words[i / cells_width * words_per_row + i % cells_width / 32] |=
+                (cell % 2) << (i % cells_width % 32);
+        }
+        words
+    }
+
+    fn read_buffer(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> Vec<u32> {
+        let copy_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("copy_buffer"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(buffer, 0, &copy_buffer, 0, copy_buffer.size());
+        queue.submit(std::iter::once(encoder.finish()));
+        copy_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, Result::unwrap);
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+        let result = bytemuck::cast_slice(&copy_buffer.slice(..).get_mapped_range()).to_vec();
+        copy_buffer.unmap();
+        result
+    }
+
+    #[test]
+    fn test_transforms() {
+        use wgpu::util::DeviceExt;
+
+        let (device, queue) = testing::device();
+        let transformer = Transformer::new(&device);
+
+        // A 3x2 grid, with y = 0 at the bottom:
+        // 4 5 6
+        // 1 2 3
+        let cells = [1_u32, 2, 3, 4, 5, 6];
+        let source = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&cells),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        for (transform, expected) in [
+            (Transform::rotate_clockwise(0, 3, 2), [1, 2, 3, 4, 5, 6]),
+            (Transform::rotate_clockwise(1, 3, 2), [3, 6, 2, 5, 1, 4]),
+            (Transform::rotate_clockwise(2, 3, 2), [6, 5, 4, 3, 2, 1]),
+            (Transform::rotate_clockwise(3, 3, 2), [4, 1, 5, 2, 6, 3]),
+            (Transform::mirror_horizontally(3), [3, 2, 1, 6, 5, 4]),
+            (Transform::mirror_vertically(2), [4, 5, 6, 1, 2, 3]),
+            (Transform::translate(1, 1), [6, 4, 5, 3, 1, 2]),
+        ] {
+            let destination_size = transform.destination_size(3, 2);
+            for storage in [CellStorage::Ages, CellStorage::Bits] {
+                let (source, expected) = match storage {
+                    CellStorage::Ages => (&source, expected.to_vec()),
+                    CellStorage::Bits => (
+                        &device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: None,
+                            contents: bytemuck::cast_slice(&pack_bits(&cells, 3)),
+                            usage: wgpu::BufferUsages::STORAGE,
+                        }),
+                        pack_bits(&expected, destination_size.0 as usize),
+                    ),
+                };
+                let destination = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: storage.buffer_size(destination_size.0, destination_size.1),
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                });
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                transformer
+                    .enqueue(
+                        &device,
+                        &mut encoder,
+                        &transform,
+                        storage,
+                        source,
+                        (3, 2),
+                        &destination,
+                        destination_size,
+                    )
+                    .unwrap();
+                queue.submit(std::iter::once(encoder.finish()));
+                assert_eq!(
+                    read_buffer(&device, &queue, &destination),
+                    expected,
+                    "{transform:?} {storage:?}"
+                );
+            }
+        }
+
+        // Transforms enqueued in the same submission each keep their own uniforms:
+        let buffer = |size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let (rotated, rotated_twice) = (buffer(24), buffer(24));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        for (transform, source, source_size, destination) in [
+            (
+                Transform::rotate_clockwise(1, 3, 2),
+                &source,
+                (3, 2),
+                &rotated,
+            ),
+            (
+                Transform::mirror_vertically(3),
+                &rotated,
+                (2, 3),
+                &rotated_twice,
+            ),
+        ] {
+            let destination_size = transform.destination_size(source_size.0, source_size.1);
+            transformer
+                .enqueue(
+                    &device,
+                    &mut encoder,
+                    &transform,
+                    CellStorage::Ages,
+                    source,
+                    source_size,
+                    destination,
+                    destination_size,
+                )
+                .unwrap();
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        assert_eq!(
+            read_buffer(&device, &queue, &rotated_twice),
+            [1, 4, 2, 5, 3, 6]
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        assert!(
+            transformer
+                .enqueue(
+                    &device,
+                    &mut encoder,
+                    &Transform::translate(0, 0),
+                    CellStorage::Ages,
+                    &source,
+                    (3, 2),
+                    &buffer(16),
+                    (3, 2),
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        use std::sync::{Arc, Mutex};
+        use wgpu::util::DeviceExt;
+
+        let (device, queue) = testing::device();
+        let transformer = Transformer::new(&device);
+
+        // Wide enough for a row of bits to span two words:
+        let (width, height) = (40, 16);
+        for (cells, expected) in [
+            (vec![0_u32; width * height], None),
+            (
+                (0..width * height)
+                    .map(|i| u32::from(i == 3 * width + 5 || i == 9 * width + 34))
+                    .collect::<Vec<_>>(),
+                Some(Rect {
+                    x: 5,
+                    y: 3,
+                    width: 30,
+                    height: 7,
+                }),
+            ),
+        ] {
+            for storage in [CellStorage::Ages, CellStorage::Bits] {
+                let contents = match storage {
+                    CellStorage::Ages => cells.clone(),
+                    CellStorage::Bits => pack_bits(&cells, width),
+                };
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::cast_slice(&contents),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+                let result = Arc::new(Mutex::new(None));
+                let result_in_callback = Arc::clone(&result);
+                transformer.read_bounding_box(
+                    &device,
+                    &queue,
+                    storage,
+                    &buffer,
+                    (width as u32, height as u32),
+                    move |bounds| {
+                        *result_in_callback.lock().unwrap() = Some(bounds);
+                    },
+                );
+                assert_eq!(*result.lock().unwrap(), Some(expected), "{storage:?}");
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use crate::State;
+use crate::computer::CellStorage;
+use crate::oscillation::OscillationAction;
+use crate::topology::Topology;
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
 use wgpu::web_sys;
@@ -8,14 +11,32 @@ use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
 use winit::platform::web::WindowAttributesExtWebSys;
 use winit::window::WindowId;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum CustomWinitEvent {
     RuleChange(u32),
-    SizeChange(u32),
+    RulestringChange(String),
+    TopologyChange(u32),
+    SizeChange(u32, u32),
+    FitGridToWindow,
+    CellStorageChange(bool),
     SetDensity(u8),
     SetGenerationsPerSecond(u8),
+    TurboChange(bool),
+    AdvanceTo(u64),
+    Step,
+    StepSizeChange(u32, u32),
+    CancelAdvance,
+    RewindTo(u64),
+    Undo,
+    HistoryLimitsChange(u32, u32),
+    OscillationActionChange(u32),
+    TakeCensus,
+    SaveSession,
+    LoadSession(Vec<u8>),
     Reset,
     TogglePause,
+    /// A batch of generations of [`State::advance_to()`] has been computed.
+    AdvanceBatchDone,
 }
 
 thread_local! {
@@ -27,14 +48,52 @@ extern "C" {
     #[wasm_bindgen(js_name = setNewState)]
     pub fn set_new_state(
         rule_idx: u32,
+        rulestring: &str,
         cells_width: u32,
+        cells_height: u32,
+        topology_idx: u32,
+        bits: bool,
         seed: u32,
         density: u8,
         paused: bool,
         generations_per_second: u8,
+        turbo: bool,
+        step_base: u32,
+        step_exponent: u32,
         frame: u64,
+        population: u32,
+        births: u32,
+        deaths: u32,
     );
 
+    /// Show the progress of advancing to the `target` generation, which is 0 when done.
+    #[wasm_bindgen(js_name = setProgress)]
+    pub fn set_progress(generation: u64, target: u64);
+
+    /// Show the range of generations that can be rewound to, and the current generation.
+    #[wasm_bindgen(js_name = setTimeline)]
+    pub fn set_timeline(oldest: u64, latest: u64, current: u64);
+
+    /// Show what happens once the grid repeats, and the period and generation it repeats
+    /// from, where the period is 0 until it does.
+    #[wasm_bindgen(js_name = setOscillation)]
+    pub fn set_oscillation(action_idx: u32, period: u64, generation: u64);
+
+    /// Show a census of the objects at a generation, as given by [`crate::census::Census::to_json()`].
+    #[wasm_bindgen(js_name = showCensus)]
+    pub fn show_census(generation: u64, json: &str);
+
+    /// Store a session saved by [`State::save_session()`].
+    #[wasm_bindgen(js_name = storeSession)]
+    pub fn store_session(bytes: &[u8]);
+
+    /// Request the stored session, which is passed to [`load_session()`] if there is one.
+    #[wasm_bindgen(js_name = requestStoredSession)]
+    pub fn request_stored_session();
+
+    #[wasm_bindgen(js_name = promptGeneration)]
+    pub fn prompt_generation();
+
     #[wasm_bindgen(js_name = toggleFullscreen)]
     pub fn toggle_fullscreen();
 
@@ -43,6 +102,22 @@ extern "C" {
 
     #[wasm_bindgen(js_name = downloadImage)]
     pub fn download_image();
+
+    #[wasm_bindgen(js_name = copyToClipboard)]
+    pub fn copy_to_clipboard(text: &str);
+}
+
+/// Continue [`State::advance_to()`] from the event loop once a batch has been computed.
+fn advance_batch_done() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::AdvanceBatchDone)
+                .ok();
+        }
+    });
 }
 
 #[wasm_bindgen(js_name = "setNewRule")]
@@ -58,14 +133,76 @@ pub fn set_new_rule(rule_idx: u32) {
     });
 }
 
+#[wasm_bindgen(js_name = "setRulestring")]
+pub fn set_rulestring(rulestring: String) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::RulestringChange(rulestring))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "setTopology")]
+pub fn set_topology(topology_idx: u32) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::TopologyChange(topology_idx))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "getTopologies")]
+pub fn get_topologies() -> JsValue {
+    JsValue::from(
+        Topology::ALL
+            .iter()
+            .map(|topology| JsValue::from(topology.name()))
+            .collect::<js_sys::Array>(),
+    )
+}
+
 #[wasm_bindgen(js_name = "setNewSize")]
-pub fn set_new_size(size: u32) {
+pub fn set_new_size(width: u32, height: u32) {
     EVENT_LOOP_PROXY.with(|proxy| {
         if let Ok(unlocked) = proxy.lock()
             && let Some(event_loop_proxy) = &*unlocked
         {
             event_loop_proxy
-                .send_event(CustomWinitEvent::SizeChange(size))
+                .send_event(CustomWinitEvent::SizeChange(width, height))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "fitGridToWindow")]
+pub fn fit_grid_to_window() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::FitGridToWindow)
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "setCellStorage")]
+pub fn set_cell_storage(bits: bool) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::CellStorageChange(bits))
                 .ok();
         }
     });
@@ -123,6 +260,159 @@ pub fn set_generations_per_second(generations_per_second: u8) {
     });
 }
 
+#[wasm_bindgen(js_name = "setTurbo")]
+pub fn set_turbo(turbo: bool) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::TurboChange(turbo))
+                .ok();
+        }
+    });
+}
+
+/// Compute generations as fast as possible until reaching `generation`, and pause there.
+#[wasm_bindgen(js_name = "stepTo")]
+pub fn step_to(generation: f64) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::AdvanceTo(generation as u64))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "step")]
+pub fn step() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy.send_event(CustomWinitEvent::Step).ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "setStepSize")]
+pub fn set_step_size(base: u32, exponent: u32) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::StepSizeChange(base, exponent))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "cancelStepTo")]
+pub fn cancel_step_to() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::CancelAdvance)
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "rewindTo")]
+pub fn rewind_to(generation: f64) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::RewindTo(generation as u64))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "undo")]
+pub fn undo() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy.send_event(CustomWinitEvent::Undo).ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "setHistoryLimits")]
+pub fn set_history_limits(cadence: u32, megabytes: u32) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::HistoryLimitsChange(cadence, megabytes))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "setOscillationAction")]
+pub fn set_oscillation_action(action_idx: u32) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::OscillationActionChange(action_idx))
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "takeCensus")]
+pub fn take_census() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::TakeCensus)
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "saveSession")]
+pub fn save_session() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::SaveSession)
+                .ok();
+        }
+    });
+}
+
+#[wasm_bindgen(js_name = "loadSession")]
+pub fn load_session(bytes: Vec<u8>) {
+    EVENT_LOOP_PROXY.with(|proxy| {
+        if let Ok(unlocked) = proxy.lock()
+            && let Some(event_loop_proxy) = &*unlocked
+        {
+            event_loop_proxy
+                .send_event(CustomWinitEvent::LoadSession(bytes))
+                .ok();
+        }
+    });
+}
+
 struct WebApp {
     state: Option<State>,
 }
@@ -148,8 +438,29 @@ impl ApplicationHandler<CustomWinitEvent> for WebApp {
             CustomWinitEvent::RuleChange(new_rule_idx) => {
                 state.set_rule_idx(new_rule_idx);
             }
-            CustomWinitEvent::SizeChange(size) => {
-                state.reset_with_cells_width(size, size);
+            CustomWinitEvent::RulestringChange(rulestring) => {
+                if let Err(e) = state.set_rulestring(&rulestring) {
+                    log::error!("{e}");
+                    state.inform_ui_about_state();
+                }
+            }
+            CustomWinitEvent::TopologyChange(topology_idx) => {
+                if let Some(&topology) = Topology::ALL.get(topology_idx as usize) {
+                    state.set_topology(topology);
+                }
+            }
+            CustomWinitEvent::SizeChange(width, height) => {
+                state.reset_with_size(width, height);
+            }
+            CustomWinitEvent::FitGridToWindow => {
+                state.fit_grid_to_window();
+            }
+            CustomWinitEvent::CellStorageChange(bits) => {
+                state.set_cell_storage(if bits {
+                    CellStorage::Bits
+                } else {
+                    CellStorage::Ages
+                });
             }
             CustomWinitEvent::SetDensity(new_density) => {
                 state.set_initial_density(new_density);
@@ -163,6 +474,49 @@ impl ApplicationHandler<CustomWinitEvent> for WebApp {
             CustomWinitEvent::SetGenerationsPerSecond(gps) => {
                 state.set_generations_per_second(gps);
             }
+            CustomWinitEvent::TurboChange(turbo) => {
+                state.set_turbo(turbo);
+            }
+            CustomWinitEvent::AdvanceTo(generation) => {
+                state.advance_to(generation);
+            }
+            CustomWinitEvent::Step => {
+                state.step();
+            }
+            CustomWinitEvent::StepSizeChange(base, exponent) => {
+                state.set_step_size(base, exponent);
+            }
+            CustomWinitEvent::CancelAdvance => {
+                state.cancel_advance();
+            }
+            CustomWinitEvent::AdvanceBatchDone => {
+                state.continue_advance();
+            }
+            CustomWinitEvent::RewindTo(generation) => {
+                state.rewind_to(generation);
+            }
+            CustomWinitEvent::Undo => {
+                state.undo();
+            }
+            CustomWinitEvent::HistoryLimitsChange(cadence, megabytes) => {
+                state.set_history_limits(u64::from(cadence), u64::from(megabytes) << 20);
+            }
+            CustomWinitEvent::TakeCensus => {
+                state.take_census();
+            }
+            CustomWinitEvent::OscillationActionChange(action_idx) => {
+                if let Some(&action) = OscillationAction::ALL.get(action_idx as usize) {
+                    state.set_oscillation_action(action);
+                }
+            }
+            CustomWinitEvent::SaveSession => {
+                state.store_session();
+            }
+            CustomWinitEvent::LoadSession(bytes) => {
+                if let Err(e) = state.load_session(&bytes) {
+                    log::error!("{e}");
+                }
+            }
         }
     }
 
@@ -181,7 +535,10 @@ impl ApplicationHandler<CustomWinitEvent> for WebApp {
 #[wasm_bindgen]
 pub async fn run(
     rule_idx: Option<u32>,
-    size: Option<u32>,
+    topology_idx: Option<u32>,
+    width: Option<u32>,
+    height: Option<u32>,
+    bits: bool,
     seed: Option<u32>,
     initial_density: Option<u8>,
     paused: bool,
@@ -222,10 +579,16 @@ pub async fn run(
     // For now, use the deprecated API to create window before event loop
     #[allow(deprecated)]
     let window = event_loop.create_window(window_attributes).unwrap();
-    let state = State::new(
+    let mut state = State::new(
         window,
         rule_idx,
-        size,
+        topology_idx.and_then(|idx| Topology::ALL.get(idx as usize).copied()),
+        width.zip(height),
+        Some(if bits {
+            CellStorage::Bits
+        } else {
+            CellStorage::Ages
+        }),
         seed,
         initial_density,
         paused,
@@ -233,6 +596,7 @@ pub async fn run(
     )
     .await
     .unwrap();
+    state.set_wake_up(advance_batch_done);
 
     let app = WebApp { state: Some(state) };
 